@@ -0,0 +1,158 @@
+//! `config.toml`: defaults and named profiles that sit beneath explicit command-line flags.
+//!
+//! ```toml
+//! [defaults]
+//! overwrite = "skip"
+//! max_memory = "512M"
+//!
+//! [profiles.release]
+//! preset = "max"
+//! format = "tar.zst"
+//! exclude = ["**/target/**", "**/.git/**"]
+//! ```
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use zipx_core::pipeline::CompressionProfile;
+
+use crate::{parse_size, Overwrite};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Applied to every command.
+    pub defaults: Settings,
+    /// Selected with `--profile NAME`, layered over `defaults`.
+    pub profiles: BTreeMap<String, Settings>,
+}
+
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    /// Built-in preset (`fast`, `balanced`, `max`) supplying codec tuning.
+    pub preset: Option<CompressionProfile>,
+    pub format: Option<String>,
+    pub level: Option<u32>,
+    pub threads: Option<u32>,
+    pub concurrency: Option<usize>,
+    pub overwrite: Option<Overwrite>,
+    /// Byte count or a size string such as `"512M"`.
+    pub max_memory: Option<Size>,
+    pub include: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Size {
+    Bytes(u64),
+    Text(String),
+}
+
+impl Settings {
+    /// `self` with every field that `over` sets replaced.
+    fn overlay(self, over: Settings) -> Settings {
+        Settings {
+            preset: over.preset.or(self.preset),
+            format: over.format.or(self.format),
+            level: over.level.or(self.level),
+            threads: over.threads.or(self.threads),
+            concurrency: over.concurrency.or(self.concurrency),
+            overwrite: over.overwrite.or(self.overwrite),
+            max_memory: over.max_memory.or(self.max_memory),
+            include: over.include.or(self.include),
+            exclude: over.exclude.or(self.exclude),
+        }
+    }
+
+    pub fn max_memory(&self) -> Result<Option<u64>, String> {
+        match &self.max_memory {
+            None => Ok(None),
+            Some(Size::Bytes(bytes)) => Ok(Some(*bytes)),
+            Some(Size::Text(text)) => parse_size(text).map(Some),
+        }
+    }
+
+    /// Archive format for compression: `explicit`, else the configured format, else the
+    /// preset's, else tar.zst.
+    pub fn compress_format(&self, explicit: Option<String>) -> String {
+        explicit
+            .or_else(|| self.format.clone())
+            .or_else(|| self.preset.map(|p| p.format().to_string()))
+            .unwrap_or_else(|| "tar.zst".to_string())
+    }
+}
+
+impl Config {
+    /// Load from `path`, else `$ZIPX_CONFIG`, else `zipx/config.toml` in the user config
+    /// directory. Only a missing file at the implicit default location is tolerated.
+    pub fn load(path: Option<&Path>) -> Result<Config, Box<dyn std::error::Error>> {
+        let (path, explicit) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match std::env::var_os("ZIPX_CONFIG") {
+                Some(path) => (PathBuf::from(path), true),
+                None => match default_path() {
+                    Some(path) => (path, false),
+                    None => return Ok(Config::default()),
+                },
+            },
+        };
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(e) if !explicit && e.kind() == std::io::ErrorKind::NotFound => {
+                return Ok(Config::default())
+            }
+            Err(e) => return Err(format!("{}: {e}", path.display()).into()),
+        };
+        toml::from_str(&text).map_err(|e| format!("{}: {e}", path.display()).into())
+    }
+
+    /// The defaults overlaid with profile `name`. The built-in preset names work without being
+    /// declared, and a declared profile with one of those names inherits that preset.
+    pub fn settings(&self, name: Option<&str>) -> Result<Settings, String> {
+        let Some(name) = name else {
+            return Ok(self.defaults.clone());
+        };
+        let builtin = builtin_preset(name);
+        let mut selected = match self.profiles.get(name) {
+            Some(profile) => profile.clone(),
+            None if builtin.is_some() => Settings::default(),
+            None => {
+                let known: Vec<&str> = ["fast", "balanced", "max"]
+                    .into_iter()
+                    .chain(
+                        self.profiles
+                            .keys()
+                            .map(String::as_str)
+                            .filter(|k| builtin_preset(k).is_none()),
+                    )
+                    .collect();
+                return Err(format!(
+                    "unknown profile {name:?} (available: {})",
+                    known.join(", ")
+                ));
+            }
+        };
+        selected.preset = selected.preset.or(builtin);
+        Ok(self.defaults.clone().overlay(selected))
+    }
+}
+
+fn builtin_preset(name: &str) -> Option<CompressionProfile> {
+    match name {
+        "fast" => Some(CompressionProfile::Fast),
+        "balanced" => Some(CompressionProfile::Balanced),
+        "max" => Some(CompressionProfile::Max),
+        _ => None,
+    }
+}
+
+fn default_path() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))?;
+    Some(base.join("zipx").join("config.toml"))
+}
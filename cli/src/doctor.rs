@@ -0,0 +1,152 @@
+//! `zipx doctor`: what this build can do and what the machine offers, for support triage.
+
+use std::path::Path;
+use std::process::Command;
+
+use zipx_core::codecs::compressor_from_name;
+use zipx_core::containers::ExtractOptions;
+use zipx_core::pipeline::{CompressOptions, Extractor};
+
+use crate::human_size;
+
+/// Formats `compress` can produce and so can be round-tripped by the self-test.
+const WRITABLE: [&str; 7] = [
+    "tar.zst", "tar.lz4", "tar.br", "tar.gz", "tar.xz", "tar.bz2", "tar",
+];
+
+/// Print the report; returns whether every self-test passed.
+pub async fn run(extractor: &Extractor) -> bool {
+    println!("zipx {}", env!("CARGO_PKG_VERSION"));
+    println!(
+        "  platform: {}-{}",
+        std::env::consts::OS,
+        std::env::consts::ARCH
+    );
+
+    println!("\nFormats");
+    for format in extractor.formats() {
+        let mode = if WRITABLE.contains(&format) {
+            "read/write"
+        } else {
+            "read"
+        };
+        println!("  {format:<8} {mode}");
+    }
+    println!("\nCodecs");
+    for codec in ["zstd", "lz4", "brotli", "gzip", "xz", "bzip2"] {
+        let status = if compressor_from_name(codec).is_some() {
+            "ok"
+        } else {
+            "missing"
+        };
+        println!("  {codec:<8} {status}");
+    }
+
+    println!("\nExternal helpers");
+    for helper in ["7za", "7z"] {
+        match helper_version(helper) {
+            Some(version) => println!("  {helper:<8} {version}"),
+            None => println!("  {helper:<8} not found"),
+        }
+    }
+
+    println!("\nResources");
+    println!("  cpus:     {}", num_cpus::get());
+    match memory() {
+        Some((total, available)) => {
+            println!(
+                "  memory:   {} total, {} available",
+                human_size(total),
+                human_size(available)
+            )
+        }
+        None => println!("  memory:   unknown"),
+    }
+
+    println!("\nSelf-test");
+    let mut healthy = true;
+    for format in WRITABLE {
+        match round_trip(extractor, format).await {
+            Ok(()) => println!("  {format:<8} ok"),
+            Err(e) => {
+                healthy = false;
+                println!("  {format:<8} FAILED: {e}");
+            }
+        }
+    }
+    healthy
+}
+
+/// First non-empty line `helper` prints when run without arguments, if it runs at all.
+fn helper_version(helper: &str) -> Option<String> {
+    let output = Command::new(helper).output().ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    Some(
+        text.lines()
+            .map(str::trim)
+            .find(|l| !l.is_empty())
+            .unwrap_or("found")
+            .to_string(),
+    )
+}
+
+/// `(total, available)` bytes of RAM, on Linux.
+fn memory() -> Option<(u64, u64)> {
+    let meminfo = std::fs::read_to_string("/proc/meminfo").ok()?;
+    let field = |name: &str| {
+        meminfo
+            .lines()
+            .find_map(|l| l.strip_prefix(name))
+            .and_then(|v| v.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+            .map(|kib| kib * 1024)
+    };
+    Some((field("MemTotal:")?, field("MemAvailable:")?))
+}
+
+/// Compress a few generated files as `format`, extract them again and compare.
+async fn round_trip(extractor: &Extractor, format: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let work = tempfile::tempdir()?;
+    let source = work.path().join("source");
+    let files = [
+        ("hello.txt", b"hello from zipx doctor\n".repeat(64)),
+        (
+            "nested/data.bin",
+            (0..=255u8).cycle().take(64 * 1024).collect(),
+        ),
+        ("nested/empty", Vec::new()),
+    ];
+    for (name, contents) in &files {
+        let path = source.join(name);
+        std::fs::create_dir_all(path.parent().unwrap_or(Path::new(".")))?;
+        std::fs::write(path, contents)?;
+    }
+
+    let archive = work.path().join(format!("selftest.{format}"));
+    extractor
+        .compress(CompressOptions {
+            sources: vec![source],
+            destination: archive.clone(),
+            format: format.to_string(),
+            ..Default::default()
+        })
+        .await?;
+
+    let output = work.path().join("output");
+    let reader = tokio::io::BufReader::new(tokio::fs::File::open(&archive).await?);
+    extractor
+        .extract(
+            format,
+            reader,
+            ExtractOptions {
+                destination: output.clone(),
+                ..Default::default()
+            },
+        )
+        .await?;
+    for (name, contents) in &files {
+        if std::fs::read(output.join(name))? != *contents {
+            return Err(format!("{name} differs after round trip").into());
+        }
+    }
+    Ok(())
+}
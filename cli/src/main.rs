@@ -1,16 +1,59 @@
-use std::path::PathBuf;
-
-use clap::{Parser, Subcommand};
-use tokio::io::BufReader;
-use tracing_subscriber::EnvFilter;
-use zipx_core::containers::ExtractOptions;
-use zipx_core::format_detection;
-use zipx_core::pipeline::{CompressOptions, Extractor};
+mod config;
+mod doctor;
+#[cfg(unix)]
+mod mount;
+#[cfg(feature = "otel")]
+mod otel;
+mod progress;
+mod tui;
+
+use std::collections::BTreeMap;
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Deserialize;
+use tokio::io::{AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio_util::sync::CancellationToken;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer};
+use zipx_core::codecs;
+use zipx_core::containers::{
+    EntrySink, ExtractOptions, LinkPolicy, MismatchPolicy, OverwritePolicy, PathPolicy,
+    PlannedEntry, StreamFormat,
+};
+use zipx_core::dedup::ChunkStore;
+use zipx_core::delta::{DirManifest, ManifestPlacement};
+use zipx_core::errors::ExtractError;
+use zipx_core::format_detection::{self, DetectedFormat};
+use zipx_core::manifest::{self, CompressJob, ExtractJob};
+use zipx_core::pipeline::{
+    replace_file, BatchCompressReport, BatchExtractReport, BatchOrder, CompressOptions,
+    CompressReport, Extractor, FollowPolicy,
+};
+use zipx_core::reader::{EntryInfo, EntryKind};
+use zipx_core::remote::{self, HttpSource, SftpConnection, SftpUrl};
 use zipx_core::resilience::IntegrityPolicy;
+#[cfg(feature = "s3")]
+use zipx_core::s3;
+use zipx_core::telemetry;
+
+use crate::config::Config;
+use crate::progress::ProgressLine;
 
 #[derive(Parser)]
 #[command(name = "zipx", version = "0.1.0", author = "ZipX Team", about = "High-throughput extractor CLI")]
 struct Cli {
+    /// Config file [default: $ZIPX_CONFIG, or ~/.config/zipx/config.toml]
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+    /// Serve Prometheus metrics on this address while running, e.g. 127.0.0.1:9464
+    #[arg(long, global = true, value_name = "ADDR")]
+    metrics_addr: Option<String>,
+    /// Write Prometheus metrics to this file on exit, for node_exporter's textfile collector
+    #[arg(long, global = true, value_name = "FILE")]
+    metrics_file: Option<PathBuf>,
     #[command(subcommand)]
     command: Commands,
 }
@@ -19,189 +62,2123 @@ struct Cli {
 enum Commands {
     /// Extract an archive to a destination directory
     Extract {
+        /// Archive to read, `-` for stdin, an http(s):// URL, an s3://bucket/key (in builds with
+        /// the `s3` feature) or an sftp://[user@]host[:port]/path. Zip archives over HTTP and S3
+        /// are read with range requests, fetching only the selected entries.
         #[arg(short, long)]
         input: PathBuf,
-        #[arg(short, long)]
-        output: PathBuf,
+        #[arg(short, long, required_unless_present = "to_stdout")]
+        output: Option<PathBuf>,
         #[arg(long, default_value = "auto")]
         format: String,
         #[arg(long, default_value = "zstd")]
         codec: String,
-        #[arg(long, default_value_t = 4)]
-        concurrency: usize,
+        /// [default: 4]
+        #[arg(long)]
+        concurrency: Option<usize>,
         #[arg(long, help = "Auto-detect format from file")]
         auto: bool,
+        #[command(flatten)]
+        password: PasswordArgs,
+        /// List what would be extracted without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// What to do when a file already exists in the destination [default: force]
+        #[arg(long, value_enum)]
+        overwrite: Option<Overwrite>,
+        /// Strip N leading path components from entry names
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        strip_components: usize,
+        /// Only extract entries matching this glob (repeatable)
+        #[arg(long)]
+        include: Option<Vec<String>>,
+        /// Skip entries matching this glob (repeatable); `!` in front keeps them after all
+        #[arg(long)]
+        exclude: Option<Vec<String>>,
+        /// Skip entries matching the patterns of this .gitignore-style file
+        #[arg(long, value_name = "FILE")]
+        exclude_from: Option<PathBuf>,
+        /// Continue an interrupted extraction, skipping entries it already finished
+        #[arg(long, conflicts_with = "atomic")]
+        resume: bool,
+        /// Extract into a staging directory and move the files into place only on success
+        #[arg(long)]
+        atomic: bool,
+        /// Fail on entries whose path would land outside the output directory instead of
+        /// skipping them with a warning
+        #[arg(long)]
+        reject_unsafe_paths: bool,
+        /// Check the extracted files against the manifest the archive was compressed with
+        #[arg(long)]
+        verify_manifest: bool,
+        /// Manifest to check against instead of the archive's own, e.g. a sidecar one
+        #[arg(long, value_name = "PATH")]
+        manifest_file: Option<PathBuf>,
+        /// Read every extracted file back and check its size and CRC-32 against the archive
+        #[arg(long)]
+        verify_after_write: bool,
+        /// Keep zip entries whose CRC-32 doesn't match, with a warning, instead of failing
+        #[arg(long)]
+        warn_crc_mismatch: bool,
+        /// Skip damaged entries, and damaged regions of tar archives, with a warning instead of
+        /// failing
+        #[arg(long)]
+        salvage: bool,
+        /// What to do with symlink and hard link entries
+        #[arg(long, value_enum, default_value = "preserve")]
+        links: Links,
+        /// Fail rather than write more than this much, e.g. 10G
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        max_output: Option<u64>,
+        /// Fail rather than extract more than this many entries
+        #[arg(long, value_name = "N")]
+        max_entries: Option<u64>,
+        /// Fail once data expands more than this many times over its compressed size
+        #[arg(long, value_name = "RATIO", value_parser = parse_ratio)]
+        max_ratio: Option<f64>,
+        /// Leave permission bits to the umask instead of restoring the archive's
+        #[arg(long)]
+        no_preserve_permissions: bool,
+        /// Give extracted files the current time instead of the archive's
+        #[arg(long)]
+        no_preserve_mtime: bool,
+        /// Restore file owners recorded in the archive (usually needs root)
+        #[arg(long)]
+        same_owner: bool,
+        /// Profile from the config file supplying defaults for these flags
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+        /// Memory budget for buffering, e.g. 512M or 2G; beyond it temporary files are used
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        max_memory: Option<u64>,
+        /// Write matched files to stdout instead of the output directory
+        #[arg(short = 'O', long, conflicts_with = "dry_run")]
+        to_stdout: bool,
+        /// How --to-stdout lays out the files
+        #[arg(long, value_enum, default_value_t = StdoutFormat::Raw, requires = "to_stdout")]
+        stdout_format: StdoutFormat,
+        /// Zstd dictionary the archive was compressed with
+        #[arg(long, value_name = "FILE")]
+        dictionary: Option<PathBuf>,
     },
     /// Compress files/directories into an archive
     Compress {
+        /// Files or directories to archive, each under its own name; a lone directory has its
+        /// contents archived at the top level instead. `-` compresses stdin, as with --raw
+        #[arg(value_name = "INPUT", required_unless_present = "input")]
+        inputs: Vec<PathBuf>,
+        /// Same as an INPUT (repeatable)
+        #[arg(short, long)]
+        input: Vec<PathBuf>,
+        /// Archive to write, `-` for stdout, an s3://bucket/key (in builds with the `s3`
+        /// feature) or an sftp://[user@]host[:port]/path
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Archive format [default: tar.zst, or the profile's format]
+        #[arg(long)]
+        format: Option<String>,
+        #[arg(long)]
+        level: Option<u32>,
+        /// Profile from the config file, or a built-in preset: fast, balanced, max
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+        /// Only add files matching this glob (repeatable)
+        #[arg(long)]
+        include: Option<Vec<String>>,
+        /// Leave out files matching this glob (repeatable); `!` in front keeps them after all
+        #[arg(long)]
+        exclude: Option<Vec<String>>,
+        /// Leave out files matching the patterns of this .gitignore-style file
+        #[arg(long, value_name = "FILE")]
+        exclude_from: Option<PathBuf>,
+        /// Leave out what .gitignore files list, and .git directories
+        #[arg(long)]
+        gitignore: bool,
+        /// Leave directory entries out, so empty directories aren't archived
+        #[arg(long)]
+        no_dirs: bool,
+        /// What to do with symlinks in the input
+        #[arg(long, value_enum, default_value = "store")]
+        follow_symlinks: Follow,
+        /// Give the same input the same archive byte for byte; mtimes are capped at
+        /// SOURCE_DATE_EPOCH, or zeroed without it
+        #[arg(long)]
+        deterministic: bool,
+        /// After the summary, list the largest files, bytes per extension and where the
+        /// time went
+        #[arg(long)]
+        stats: bool,
+        /// List what would be archived without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Memory budget for buffering, e.g. 512M or 2G; beyond it temporary files are used
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        max_memory: Option<u64>,
+        /// Record each file's size, mtime and BLAKE3 in a manifest, inside the archive or
+        /// beside it as <output>.manifest.json
+        #[arg(long, value_enum, value_name = "WHERE")]
+        manifest: Option<Manifest>,
+        /// Write tar.zst in the seekable zstd format, so listing it and extracting single files
+        /// don't decode it from the start
+        #[arg(long)]
+        seekable: bool,
+        /// Compress against this zstd dictionary (see `dict train`); extracting then needs it
+        #[arg(long, value_name = "FILE")]
+        dictionary: Option<PathBuf>,
+        /// Compress a single file with the format's codec and no tar around it, e.g. to
+        /// notes.txt.zst; always the case for `-` (stdin)
+        #[arg(long)]
+        raw: bool,
+    },
+    /// Repack the files of an archive into another format, e.g. a zip into tar.zst, without
+    /// extracting them to disk
+    Convert {
         #[arg(short, long)]
         input: PathBuf,
         #[arg(short, long)]
         output: PathBuf,
-        #[arg(long, default_value = "tar.zst")]
-        format: String,
+        /// Format of the input archive
+        #[arg(long, default_value = "auto")]
+        from: String,
+        /// Format to write [default: tar.zst, or the profile's format]
+        #[arg(long)]
+        format: Option<String>,
+        #[command(flatten)]
+        password: PasswordArgs,
         #[arg(long)]
         level: Option<u32>,
+        /// Profile from the config file, or a built-in preset: fast, balanced, max
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+        /// Only repack entries matching this glob (repeatable)
         #[arg(long)]
         include: Option<Vec<String>>,
+        /// Leave out entries matching this glob (repeatable); `!` in front keeps them after all
         #[arg(long)]
         exclude: Option<Vec<String>>,
+        /// Leave out entries matching the patterns of this .gitignore-style file
+        #[arg(long, value_name = "FILE")]
+        exclude_from: Option<PathBuf>,
+        /// List what would be repacked without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Write tar.zst in the seekable zstd format
+        #[arg(long)]
+        seekable: bool,
+        /// Compress against this zstd dictionary (see `dict train`)
+        #[arg(long, value_name = "FILE")]
+        dictionary: Option<PathBuf>,
+    },
+    /// Add files or directories to an existing zip or plain tar archive in place
+    Add {
+        /// Archive to add to
+        archive: PathBuf,
+        /// Files and directories to add, each under its own name
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+    },
+    /// Take the entries matching glob patterns out of a zip or tar archive, which is rewritten
+    Remove {
+        archive: PathBuf,
+        /// Glob patterns; a matching directory takes everything below it
+        #[arg(required = true)]
+        patterns: Vec<String>,
+    },
+    /// Replace entries of a zip or tar archive with files or directories of the same names,
+    /// adding those it doesn't have; the archive is rewritten
+    Update {
+        archive: PathBuf,
+        /// Files and directories, each under its own name
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
     },
+    /// Browse an archive interactively, mark entries and extract the selection
+    Tui {
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Where marked entries go [default: next to the archive, named after it]
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        #[arg(long, default_value = "auto")]
+        format: String,
+        #[command(flatten)]
+        password: PasswordArgs,
+    },
+    /// Mount an archive as a read-only filesystem until Ctrl-C (FUSE; Linux and macOS only,
+    /// no WinFsp support on Windows)
+    Mount {
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Empty directory to mount on
+        mountpoint: PathBuf,
+        #[arg(long, default_value = "auto")]
+        format: String,
+        #[command(flatten)]
+        password: PasswordArgs,
+        /// Memory budget per open file, e.g. 64M; larger files are staged in temporary files
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        max_memory: Option<u64>,
+    },
+    /// List the entries of an archive without extracting it
+    List {
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(long, default_value = "auto")]
+        format: String,
+        #[command(flatten)]
+        password: PasswordArgs,
+        /// Show the kind, permissions, size and modification time of each entry
+        #[arg(short, long)]
+        long: bool,
+        /// Print the entries as JSON
+        #[arg(long, conflicts_with = "long")]
+        json: bool,
+        /// Zstd dictionary the archive was compressed with
+        #[arg(long, value_name = "FILE")]
+        dictionary: Option<PathBuf>,
+    },
+    /// Check that every file in an archive decodes (and matches its CRC, for zip) without
+    /// extracting anything
+    Test {
+        /// Archive to check, or - for stdin
+        #[arg(short, long)]
+        input: PathBuf,
+        #[arg(long, default_value = "auto")]
+        format: String,
+        #[command(flatten)]
+        password: PasswordArgs,
+        /// Zstd dictionary the archive was compressed with
+        #[arg(long, value_name = "FILE")]
+        dictionary: Option<PathBuf>,
+    },
+    /// Show how much space each directory inside an archive takes
+    Du {
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Directory levels to break down
+        #[arg(short, long, default_value_t = 1)]
+        depth: usize,
+        #[arg(long, default_value = "auto")]
+        format: String,
+        /// Print exact byte counts instead of KiB/MiB/GiB
+        #[arg(short, long)]
+        bytes: bool,
+    },
+    /// Build zstd dictionaries for archives of many small, similar files
+    Dict {
+        #[command(subcommand)]
+        command: DictCommand,
+    },
+    /// Report compiled-in formats, external helpers and resources, and run a self-test
+    Doctor,
     /// Batch extract multiple archives
     BatchExtract {
+        /// Archives, glob patterns (`downloads/*.zip`) or directories to search
         #[arg(short, long)]
         inputs: Vec<PathBuf>,
+        /// Search directory inputs recursively
         #[arg(short, long)]
-        output_dir: PathBuf,
-        #[arg(long, default_value_t = 4)]
-        concurrency: usize,
+        recursive: bool,
+        /// Walk this directory tree and extract every archive found in it
+        #[arg(long, value_name = "DIR")]
+        scan: Option<PathBuf>,
+        /// JSON or CSV job file with per-archive input, output, format and options
+        #[arg(long, value_name = "FILE")]
+        manifest: Option<PathBuf>,
+        /// Where to extract; with --scan, defaults to next to each archive
+        #[arg(short, long, required_unless_present_any = ["scan", "manifest"])]
+        output_dir: Option<PathBuf>,
+        /// Archives extracted at once, and entries within each [default: 4]
+        #[arg(long)]
+        concurrency: Option<usize>,
+        #[command(flatten)]
+        password: PasswordArgs,
+        /// Only extract entries matching this glob (repeatable)
+        #[arg(long)]
+        include: Option<Vec<String>>,
+        /// Skip entries matching this glob (repeatable)
+        #[arg(long)]
+        exclude: Option<Vec<String>>,
+        /// Continue interrupted extractions, skipping entries they already finished
+        #[arg(long)]
+        resume: bool,
+        /// Profile from the config file supplying defaults for these flags
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+        /// Memory budget for buffering, e.g. 512M or 2G; beyond it temporary files are used
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        max_memory: Option<u64>,
     },
     /// Batch compress multiple sources
     BatchCompress {
+        /// Files, directories or glob patterns to compress, one archive each
         #[arg(short, long)]
         inputs: Vec<PathBuf>,
+        /// JSON or CSV job file with per-source input, output, format, level and filters
+        #[arg(long, value_name = "FILE")]
+        manifest: Option<PathBuf>,
+        #[arg(short, long, required_unless_present = "manifest")]
+        output_dir: Option<PathBuf>,
+        /// Archive format [default: tar.zst, or the profile's format]
+        #[arg(long)]
+        format: Option<String>,
+        #[arg(long)]
+        level: Option<u32>,
+        /// Profile from the config file, or a built-in preset: fast, balanced, max
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+        /// Memory budget for buffering, e.g. 512M or 2G; beyond it temporary files are used
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        max_memory: Option<u64>,
+        /// Sources compressed at once [default: 4]
+        #[arg(long)]
+        concurrency: Option<usize>,
+        /// Start with the sources holding the least data, so small archives finish early
+        #[arg(long)]
+        smallest_first: bool,
+    },
+    /// Run the extract and compress jobs of a manifest in order
+    Batch {
+        /// JSON, YAML or CSV job file; each job has a `kind` of extract or compress, an input
+        /// and output, and optionally format, level, include/exclude and password
+        #[arg(long, value_name = "FILE")]
+        manifest: PathBuf,
+        /// Archives or sources handled at once [default: 4]
+        #[arg(long)]
+        concurrency: Option<usize>,
+        /// Password for extract jobs that don't set their own
+        #[command(flatten)]
+        password: PasswordArgs,
+        /// Profile from the config file supplying defaults for these flags
+        #[arg(long, value_name = "NAME")]
+        profile: Option<String>,
+        /// Memory budget for buffering, e.g. 512M or 2G; beyond it temporary files are used
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        max_memory: Option<u64>,
+    },
+    /// Back up files into a deduplicating chunk store as a new snapshot; only chunks the
+    /// store doesn't have yet are written
+    Backup {
+        /// Files or directories to back up (repeatable; each goes under its own name, or a
+        /// lone directory's contents at the top level)
+        #[arg(short, long, required = true)]
+        input: Vec<PathBuf>,
+        /// Chunk store directory, created if missing
         #[arg(short, long)]
-        output_dir: PathBuf,
-        #[arg(long, default_value = "tar.zst")]
-        format: String,
+        store: PathBuf,
+        /// zstd level for new chunks [default: 3]
+        #[arg(long)]
+        level: Option<u32>,
+        #[arg(long)]
+        include: Option<Vec<String>>,
+        #[arg(long)]
+        exclude: Option<Vec<String>>,
+        /// Report what would be stored without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Restore a snapshot from a chunk store
+    Restore {
+        #[arg(short, long)]
+        store: PathBuf,
+        /// Snapshot id or unique prefix of one
+        #[arg(long, default_value = "latest")]
+        snapshot: String,
+        #[arg(short, long)]
+        output: PathBuf,
+        /// List what would be restored without writing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// What to do when a file already exists in the destination [default: force]
+        #[arg(long, value_enum)]
+        overwrite: Option<Overwrite>,
+        /// Strip N leading path components from file names
+        #[arg(long, value_name = "N", default_value_t = 0)]
+        strip_components: usize,
+        /// Only restore files matching this glob (repeatable)
+        #[arg(long)]
+        include: Option<Vec<String>>,
+        /// Skip files matching this glob (repeatable)
+        #[arg(long)]
+        exclude: Option<Vec<String>>,
+    },
+    /// List the snapshots in a chunk store
+    Snapshots {
+        #[arg(short, long)]
+        store: PathBuf,
+    },
+    /// Archive only the files added or changed in a directory since a manifest of it, plus
+    /// the list of files deleted since
+    Delta {
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Archive to write
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Manifest the delta is against; without one every file is included
+        #[arg(long, value_name = "FILE")]
+        base: Option<PathBuf>,
+        /// Write the directory's manifest as of this delta here, for the next one (may be the
+        /// same file as --base)
+        #[arg(long, value_name = "FILE")]
+        save_manifest: Option<PathBuf>,
+        /// Archive format [default: tar.zst]
+        #[arg(long)]
+        format: Option<String>,
         #[arg(long)]
         level: Option<u32>,
+        #[arg(long)]
+        include: Option<Vec<String>>,
+        #[arg(long)]
+        exclude: Option<Vec<String>>,
+        /// List what would be archived without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Apply a delta archive to the directory its base manifest describes
+    ApplyDelta {
+        #[arg(short, long)]
+        input: PathBuf,
+        /// Directory to update
+        #[arg(short, long)]
+        output: PathBuf,
+        #[arg(long, default_value = "auto")]
+        format: String,
+        #[command(flatten)]
+        password: PasswordArgs,
+        /// List what would be written without changing anything
+        #[arg(long)]
+        dry_run: bool,
     },
 }
 
+#[derive(Subcommand)]
+enum DictCommand {
+    /// Train a dictionary on the files under a directory
+    Train {
+        /// Directory of sample files, e.g. logs or JSON documents like the ones to compress
+        dir: PathBuf,
+        /// Where to write the dictionary
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Largest dictionary to produce, e.g. 64K [default: 112K]
+        #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+        max_size: Option<u64>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum StdoutFormat {
+    /// File contents concatenated
+    Raw,
+    /// A tar stream of the matched files
+    Tar,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Overwrite {
+    Skip,
+    Force,
+    Rename,
+    Newer,
+    Error,
+}
+
+impl From<Overwrite> for OverwritePolicy {
+    fn from(value: Overwrite) -> Self {
+        match value {
+            Overwrite::Skip => OverwritePolicy::Skip,
+            Overwrite::Force => OverwritePolicy::Overwrite,
+            Overwrite::Rename => OverwritePolicy::RenameWithSuffix,
+            Overwrite::Newer => OverwritePolicy::OverwriteIfNewer,
+            Overwrite::Error => OverwritePolicy::Error,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Manifest {
+    /// As the archive's first entry
+    Embedded,
+    /// In a file beside the archive
+    Sidecar,
+}
+
+impl From<Manifest> for ManifestPlacement {
+    fn from(value: Manifest) -> Self {
+        match value {
+            Manifest::Embedded => ManifestPlacement::Embedded,
+            Manifest::Sidecar => ManifestPlacement::Sidecar,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Links {
+    /// Recreate links that point inside the output directory
+    Preserve,
+    /// Leave links out, with a warning
+    Skip,
+    /// Write a copy of the file each link points at
+    Dereference,
+    /// Fail on the first link
+    Reject,
+}
+
+impl From<Links> for LinkPolicy {
+    fn from(value: Links) -> Self {
+        match value {
+            Links::Preserve => LinkPolicy::Preserve,
+            Links::Skip => LinkPolicy::SkipWithWarning,
+            Links::Dereference => LinkPolicy::Dereference,
+            Links::Reject => LinkPolicy::Reject,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum Follow {
+    /// Archive each link itself
+    Store,
+    /// Archive what each link points at, entering linked directories
+    Dereference,
+    /// Leave links out
+    Skip,
+}
+
+impl From<Follow> for FollowPolicy {
+    fn from(value: Follow) -> Self {
+        match value {
+            Follow::Store => FollowPolicy::Store,
+            Follow::Dereference => FollowPolicy::Dereference,
+            Follow::Skip => FollowPolicy::Skip,
+        }
+    }
+}
+
+#[derive(Args)]
+struct PasswordArgs {
+    /// Password for encrypted archives (visible in the process list; prefer --password-file or ZIPX_PASSWORD)
+    #[arg(long)]
+    password: Option<String>,
+    /// Read the password from the first line of a file
+    #[arg(long, conflicts_with = "password")]
+    password_file: Option<PathBuf>,
+}
+
+impl PasswordArgs {
+    /// Resolve the password from the flags, then `ZIPX_PASSWORD`.
+    fn resolve(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        if let Some(password) = &self.password {
+            return Ok(Some(password.clone()));
+        }
+        if let Some(path) = &self.password_file {
+            let contents = std::fs::read_to_string(path)?;
+            let line = contents.lines().next().unwrap_or_default();
+            return Ok(Some(line.to_string()));
+        }
+        Ok(std::env::var("ZIPX_PASSWORD").ok())
+    }
+}
+
+/// Writes the metrics to its path when dropped.
+struct MetricsFile(PathBuf);
+
+impl MetricsFile {
+    fn new(path: PathBuf) -> Self {
+        telemetry::install_metrics();
+        MetricsFile(path)
+    }
+}
+
+impl Drop for MetricsFile {
+    fn drop(&mut self) {
+        if let Err(e) = telemetry::install_metrics().write_textfile(&self.0) {
+            eprintln!(
+                "Warning: could not write metrics to {}: {e}",
+                self.0.display()
+            );
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env().add_directive("zipx_core=info".parse()?))
+    let fmt = tracing_subscriber::fmt::layer()
+        .with_filter(EnvFilter::from_default_env().add_directive("zipx_core=info".parse()?));
+    // Spans are flushed when `main` returns.
+    #[cfg(feature = "otel")]
+    let traces = otel::Exporter::from_env()?;
+    #[cfg(feature = "otel")]
+    let traces_layer = traces.as_ref().map(otel::Exporter::layer);
+    #[cfg(not(feature = "otel"))]
+    let traces_layer = None::<tracing_subscriber::layer::Identity>;
+    tracing_subscriber::registry()
+        .with(fmt)
+        .with(traces_layer)
         .init();
 
     let args = Cli::parse();
+    if let Some(addr) = &args.metrics_addr {
+        let addr = telemetry::serve_metrics(addr)?;
+        tracing::info!("serving metrics on http://{addr}/metrics");
+    }
+    // Written when `main` returns, however it returns.
+    let _metrics_file = args.metrics_file.clone().map(MetricsFile::new);
+    let config = Config::load(args.config.as_deref())?;
     let extractor = Extractor::with_defaults();
 
     match args.command {
-        Commands::Extract { input, output, format, concurrency, auto, .. } => {
+        Commands::Extract {
+            input,
+            output,
+            format,
+            concurrency,
+            auto,
+            password,
+            dry_run,
+            overwrite,
+            strip_components,
+            include,
+            exclude,
+            exclude_from,
+            resume,
+            atomic,
+            reject_unsafe_paths,
+            verify_manifest,
+            manifest_file,
+            verify_after_write,
+            warn_crc_mismatch,
+            salvage,
+            links,
+            max_output,
+            max_entries,
+            max_ratio,
+            no_preserve_permissions,
+            no_preserve_mtime,
+            same_owner,
+            profile,
+            max_memory,
+            to_stdout,
+            stdout_format,
+            dictionary,
+            ..
+        } => {
+            let settings = config.settings(profile.as_deref())?;
+            let from_stdin = is_stdio(&input);
+            let url = input
+                .to_str()
+                .filter(|input| remote::is_url(input) || remote::is_s3(input));
+            if url.is_some() && (to_stdout || resume) {
+                return Err(
+                    "--to-stdout and --resume are not supported for remote archives".into(),
+                );
+            }
+            let remote = url.map(open_remote).transpose()?;
+            let sftp_url = input
+                .to_str()
+                .filter(|input| remote::is_sftp(input))
+                .map(SftpUrl::parse)
+                .transpose()?;
+            let (mut detection, mut layers) = (None, None);
+            let mut stdin = from_stdin
+                .then(|| AsyncReadExt::chain(std::io::Cursor::new(Vec::new()), tokio::io::stdin()));
             // Auto-detect format if requested or format is "auto"
-            let detected_format = if auto || format == "auto" {
-                match format_detection::detect_format(&input) {
-                    Ok(fmt) => {
-                        println!("Detected format: {}", fmt.as_str());
-                        fmt.as_str().to_string()
+            let detected_format = if from_stdin && (auto || format == "auto") {
+                let (fmt, reader) =
+                    format_detection::detect_from_reader(tokio::io::stdin()).await?;
+                if fmt == DetectedFormat::Unknown {
+                    return Err("could not detect the format of stdin; pass --format".into());
+                }
+                eprintln!("Detected format: {}", fmt.as_str());
+                stdin = Some(reader);
+                fmt.as_str().to_string()
+            } else if let (Some(source), true) = (&remote, auto || format == "auto") {
+                let url = url.unwrap_or_default();
+                let by_name = format_detection::detect_from_extension(Path::new(
+                    url.split(['?', '#']).next().unwrap_or(url),
+                ));
+                // A zip is taken at its name, so range requests still fetch only what they need;
+                // anything else is told by its first bytes, the name being the fallback.
+                let fmt = match by_name {
+                    DetectedFormat::Zip => by_name,
+                    _ => {
+                        let mut head = Vec::new();
+                        let source = source.reopen();
+                        let head = tokio::task::spawn_blocking(move || {
+                            source
+                                .take(format_detection::PEEK_LEN as u64)
+                                .read_to_end(&mut head)
+                                .map(|_| head)
+                        })
+                        .await??;
+                        match format_detection::detect_from_bytes(&head) {
+                            DetectedFormat::Unknown => by_name,
+                            fmt => fmt,
+                        }
                     }
-                    Err(e) => {
-                        eprintln!("Warning: Could not auto-detect format: {}", e);
-                        eprintln!("Falling back to specified format: {}", format);
-                        format
+                };
+                eprintln!("Detected format: {}", fmt.as_str());
+                fmt.as_str().to_string()
+            } else if let (Some(url), true) = (
+                sftp_url.as_ref().map(|url| url.path.as_str()),
+                auto || format == "auto",
+            ) {
+                // Without downloading anything, the name is all there is to go on.
+                let path = url.split(['?', '#']).next().unwrap_or(url);
+                let fmt = format_detection::detect_from_extension(Path::new(path));
+                eprintln!("Detected format: {}", fmt.as_str());
+                fmt.as_str().to_string()
+            } else if auto || format == "auto" {
+                match format_detection::detect_layers(&input) {
+                    Ok(found)
+                        if !found.is_empty() && format_detection::format_of(&found).is_none() =>
+                    {
+                        let label = format_detection::layers_label(&found);
+                        eprintln!("Detected format: {label}");
+                        layers = Some(found);
+                        label
                     }
+                    _ => match format_detection::detect_candidates(&input) {
+                        Ok(found) => {
+                            let fmt = found.best();
+                            eprintln!("Detected format: {}", fmt.as_str());
+                            detection = Some(found);
+                            fmt.as_str().to_string()
+                        }
+                        Err(e) => {
+                            eprintln!("Warning: Could not auto-detect format: {}", e);
+                            eprintln!("Falling back to specified format: {}", format);
+                            format
+                        }
+                    },
                 }
             } else {
                 format
             };
 
-            let file = tokio::fs::File::open(&input).await?;
-            let reader = BufReader::new(file);
-            let mut options = ExtractOptions::default();
-            options.destination = output;
-            options.concurrency = concurrency;
-            options.integrity = IntegrityPolicy::strict();
-            let report = extractor.extract(&detected_format, reader, options).await?;
-            println!("Extracted {} entries ({} bytes)", report.entries, report.bytes_written);
+            let sink = to_stdout.then(|| {
+                let format = match stdout_format {
+                    StdoutFormat::Raw => StreamFormat::Raw,
+                    StdoutFormat::Tar => StreamFormat::Tar,
+                };
+                EntrySink::new(std::io::BufWriter::new(std::io::stdout()), format)
+            });
+            let (line, progress) = match dry_run {
+                false => ProgressLine::start().unzip(),
+                true => (None, None),
+            };
+            let mut options = ExtractOptions {
+                destination: output.unwrap_or_default(),
+                concurrency: concurrency.or(settings.concurrency).unwrap_or(4),
+                integrity: IntegrityPolicy {
+                    skip_bad_blocks: salvage,
+                    ..IntegrityPolicy::strict()
+                },
+                password: password.resolve()?,
+                dry_run,
+                overwrite: overwrite
+                    .or(settings.overwrite)
+                    .unwrap_or(Overwrite::Force)
+                    .into(),
+                strip_components,
+                include: include.or_else(|| settings.include.clone()),
+                exclude: exclude.or_else(|| settings.exclude.clone()),
+                exclude_from,
+                resume,
+                unsafe_paths: if reject_unsafe_paths {
+                    PathPolicy::Reject
+                } else {
+                    PathPolicy::Skip
+                },
+                links: links.into(),
+                atomic,
+                crc_mismatch: if warn_crc_mismatch {
+                    MismatchPolicy::Warn
+                } else {
+                    MismatchPolicy::Fail
+                },
+                verify_manifest,
+                manifest_file,
+                verify_after_write,
+                max_output_bytes: max_output,
+                max_entries,
+                max_compression_ratio: max_ratio,
+                preserve_permissions: !no_preserve_permissions,
+                preserve_mtime: !no_preserve_mtime,
+                preserve_ownership: same_owner,
+                max_memory: max_memory.or(settings.max_memory()?),
+                dictionary,
+                single_file_name: (!from_stdin).then(|| format_detection::archive_stem(&input)),
+                progress,
+                sink,
+                cancel: Some(cancel_on_ctrl_c()),
+                pause: None,
+            };
+            let result = loop {
+                let result = if let Some(source) = &remote {
+                    remote::extract_http(source, &detected_format, &options).await
+                } else if let Some(url) = &sftp_url {
+                    remote::extract_sftp(&extractor, url, &detected_format, options.clone()).await
+                } else if let Some(reader) = stdin.take() {
+                    extractor
+                        .extract(&detected_format, BufReader::new(reader), options.clone())
+                        .await
+                } else if let Some(layers) = &layers {
+                    extractor
+                        .extract_layers(&input, layers, options.clone())
+                        .await
+                } else if let Some(detection) = &detection {
+                    match extractor
+                        .extract_detected(&input, detection, options.clone())
+                        .await
+                    {
+                        Ok((fmt, report)) => {
+                            if fmt.as_str() != detected_format {
+                                eprintln!("Read as {} instead", fmt.as_str());
+                            }
+                            Ok(report)
+                        }
+                        Err(e) => Err(e),
+                    }
+                } else {
+                    let file = tokio::fs::File::open(&input).await?;
+                    let reader = BufReader::new(file);
+                    extractor
+                        .extract(&detected_format, reader, options.clone())
+                        .await
+                };
+                match result {
+                    // Stdin can't be replayed, so only prompt when the archive can be reopened.
+                    // Remote archives are simply fetched again.
+                    Err(ExtractError::Password)
+                        if options.password.is_none()
+                            && !from_stdin
+                            && std::io::stdin().is_terminal() =>
+                    {
+                        options.password = Some(rpassword::prompt_password("Password: ")?);
+                    }
+                    other => break other,
+                }
+            };
+            if let Some(line) = line {
+                line.finish().await;
+            }
+            let report = result?;
+            if dry_run {
+                print_plan("write", &report.planned);
+            } else if to_stdout {
+                eprintln!(
+                    "Streamed {} files ({} bytes)",
+                    report.entries, report.bytes_written
+                );
+            } else {
+                println!(
+                    "Extracted {} entries ({} bytes)",
+                    report.entries, report.bytes_written
+                );
+                if let Some(source) = &remote {
+                    println!(
+                        "Fetched {} of {}",
+                        human_size(source.bytes_fetched()),
+                        human_size(source.len())
+                    );
+                }
+                if report.reconnects > 0 {
+                    println!("Resumed after {} dropped connections", report.reconnects);
+                }
+                if report.skipped > 0 {
+                    println!("Skipped {} existing files", report.skipped);
+                }
+                if report.resumed > 0 {
+                    println!("Resumed: {} files were already extracted", report.resumed);
+                }
+            }
             if !report.warnings.is_empty() {
                 eprintln!("Warnings ({}):", report.warnings.len());
-                for w in report.warnings {
+                for w in &report.warnings {
                     eprintln!("- {w}");
                 }
             }
+            if !report.manifest_issues.is_empty() {
+                eprintln!(
+                    "Differences from the manifest ({}):",
+                    report.manifest_issues.len()
+                );
+                for issue in &report.manifest_issues {
+                    match issue.path.as_os_str().is_empty() {
+                        true => eprintln!("- archive: {}", issue.message),
+                        false => eprintln!("- {}: {}", issue.path.display(), issue.message),
+                    }
+                }
+                return Err("extracted files don't match the manifest".into());
+            }
+            if verify_after_write && !dry_run && !to_stdout {
+                let failed: Vec<_> = report.failed_write_checks().collect();
+                if failed.is_empty() {
+                    println!("Verified {} files", report.write_checks.len());
+                } else {
+                    eprintln!("Files that didn't read back as written ({}):", failed.len());
+                    for check in &failed {
+                        let problem = match (&check.error, check.actual_crc) {
+                            (Some(error), _) => error.clone(),
+                            _ if check.actual_size != check.expected_size => {
+                                format!(
+                                    "{} bytes, expected {}",
+                                    check.actual_size, check.expected_size
+                                )
+                            }
+                            (None, actual) => format!(
+                                "CRC-32 {:08x}, expected {:08x}",
+                                actual.unwrap_or_default(),
+                                check.expected_crc.unwrap_or_default()
+                            ),
+                        };
+                        eprintln!("- {}: {problem}", check.file.display());
+                    }
+                    return Err("extracted files don't match the archive".into());
+                }
+            }
         }
-        Commands::Compress { input, output, format, level, include, exclude } => {
-            let mut options = CompressOptions::default();
-            options.source = input;
-            options.destination = output;
-            options.format = format;
-            options.compression_level = level;
-            options.include = include;
-            options.exclude = exclude;
-            let report = extractor.compress(options).await?;
-            println!("Compressed {} files ({} bytes -> {} bytes, ratio: {:.2}%)",
+        Commands::Compress {
+            inputs,
+            input,
+            output,
+            format,
+            level,
+            profile,
+            include,
+            exclude,
+            exclude_from,
+            gitignore,
+            no_dirs,
+            follow_symlinks,
+            deterministic,
+            stats,
+            dry_run,
+            max_memory,
+            manifest,
+            seekable,
+            dictionary,
+            raw,
+        } => {
+            let settings = config.settings(profile.as_deref())?;
+            let sources: Vec<PathBuf> = input.into_iter().chain(inputs).collect();
+            // Tar entries need their size up front, so stdin is compressed as a bare stream.
+            let from_stdin = sources.iter().any(|source| is_stdio(source));
+            if from_stdin && sources.len() > 1 {
+                return Err("stdin input can't be combined with other inputs".into());
+            }
+            if from_stdin && (manifest.is_some() || dry_run) {
+                return Err("--manifest and --dry-run need files, not stdin".into());
+            }
+            let to_stdout = is_stdio(&output);
+            let sftp_url = output
+                .to_str()
+                .filter(|output| remote::is_sftp(output))
+                .map(SftpUrl::parse)
+                .transpose()?;
+            let s3_url = output
+                .to_str()
+                .filter(|output| remote::is_s3(output))
+                .map(str::to_string);
+            let (line, progress) = match dry_run {
+                false => ProgressLine::start().unzip(),
+                true => (None, None),
+            };
+            let options = CompressOptions {
+                sources,
+                destination: output,
+                format: settings.compress_format(format),
+                compression_level: level.or(settings.level),
+                include: include.or_else(|| settings.include.clone()),
+                exclude: exclude.or_else(|| settings.exclude.clone()),
+                exclude_from,
+                use_gitignore: gitignore,
+                preserve_dirs: !no_dirs,
+                follow_symlinks: follow_symlinks.into(),
+                dry_run,
+                deterministic,
+                max_memory: max_memory.or(settings.max_memory()?),
+                profile: settings.preset,
+                threads: settings.threads,
+                concurrency: 1,
+                batch_order: BatchOrder::AsGiven,
+                progress,
+                cancel: Some(cancel_on_ctrl_c()),
+                pause: None,
+                manifest: manifest.map(Into::into),
+                seekable,
+                dictionary,
+                raw,
+                password: None,
+            };
+            if manifest.is_some() && (to_stdout || sftp_url.is_some() || s3_url.is_some()) {
+                return Err("--manifest needs a local output file".into());
+            }
+            if dry_run {
+                let report = extractor.compress(options).await?;
+                print_plan("archive", &report.planned);
+                if stats {
+                    print_stats(&report, false);
+                }
+                return Ok(());
+            }
+            let result = async {
+                if to_stdout {
+                    compress_into(&extractor, options, from_stdin, tokio::io::stdout()).await
+                } else if let Some(url) = &sftp_url {
+                    let sftp = SftpConnection::connect(url).await?;
+                    let mut file = sftp.create(&url.path).await?;
+                    let report = compress_into(&extractor, options, from_stdin, &mut file).await?;
+                    // Closing the handle is what tells the server the upload is complete.
+                    file.shutdown().await?;
+                    Ok(report)
+                } else if let Some(url) = &s3_url {
+                    upload_to_s3(&extractor, options, from_stdin, url).await
+                } else if from_stdin {
+                    let destination = options.destination.clone();
+                    replace_file(&destination, |file| {
+                        compress_into(&extractor, options, true, file)
+                    })
+                    .await
+                } else {
+                    extractor.compress(options).await
+                }
+            }
+            .await;
+            if let Some(line) = line {
+                line.finish().await;
+            }
+            let report = result?;
+            // Keep stdout clean for the archive bytes when piping.
+            let summary = format!(
+                "Compressed {} files ({} bytes -> {} bytes, ratio: {:.2}%)",
                 report.files,
                 report.bytes_read,
                 report.bytes_written,
                 report.compression_ratio * 100.0
             );
+            if to_stdout {
+                eprintln!("{summary}");
+            } else {
+                println!("{summary}");
+            }
+            if stats {
+                print_stats(&report, to_stdout);
+            }
+        }
+        Commands::Convert {
+            input,
+            output,
+            from,
+            format,
+            password,
+            level,
+            profile,
+            include,
+            exclude,
+            exclude_from,
+            dry_run,
+            seekable,
+            dictionary,
+        } => {
+            let settings = config.settings(profile.as_deref())?;
+            let reader = zipx_core::reader::ArchiveReader::open(&input, &from)?
+                .with_password(password.resolve()?);
+            let (line, progress) = match dry_run {
+                false => ProgressLine::start().unzip(),
+                true => (None, None),
+            };
+            let options = CompressOptions {
+                destination: output,
+                format: settings.compress_format(format),
+                compression_level: level.or(settings.level),
+                include: include.or_else(|| settings.include.clone()),
+                exclude: exclude.or_else(|| settings.exclude.clone()),
+                exclude_from,
+                dry_run,
+                max_memory: settings.max_memory()?,
+                profile: settings.preset,
+                threads: settings.threads,
+                progress,
+                cancel: Some(cancel_on_ctrl_c()),
+                seekable,
+                dictionary,
+                ..Default::default()
+            };
+            let result = extractor.repack(reader, options).await;
+            if let Some(line) = line {
+                line.finish().await;
+            }
+            let report = result?;
+            if dry_run {
+                print_plan("repack", &report.planned);
+            } else {
+                println!(
+                    "Repacked {} files ({} bytes -> {} bytes, ratio: {:.2}%)",
+                    report.files,
+                    report.bytes_read,
+                    report.bytes_written,
+                    report.compression_ratio * 100.0
+                );
+            }
+        }
+        Commands::Add { archive, paths } => {
+            let report = extractor.append(&archive, &paths).await?;
+            println!(
+                "Added {} files ({} bytes) to {}",
+                report.files,
+                report.bytes_read,
+                archive.display()
+            );
+        }
+        Commands::Remove { archive, patterns } => {
+            let report = extractor.remove(&archive, &patterns).await?;
+            println!(
+                "Removed {} entries, kept {} ({} bytes)",
+                report.removed, report.kept, report.bytes_written
+            );
+        }
+        Commands::Update { archive, paths } => {
+            let report = extractor.update(&archive, &paths).await?;
+            println!(
+                "Wrote {} entries in place of {}, kept {} ({} bytes)",
+                report.added, report.removed, report.kept, report.bytes_written
+            );
+        }
+        Commands::Tui {
+            input,
+            output,
+            format,
+            password,
+        } => {
+            let format = if format == "auto" {
+                format_detection::detect_format(&input)?
+                    .as_str()
+                    .to_string()
+            } else {
+                format
+            };
+            let output = output.unwrap_or_else(|| {
+                input
+                    .parent()
+                    .unwrap_or(Path::new("."))
+                    .join(format_detection::archive_stem(&input))
+            });
+            tui::run(extractor, input, format, output, password.resolve()?).await?;
+        }
+        Commands::Mount {
+            input,
+            mountpoint,
+            format,
+            password,
+            max_memory,
+        } => {
+            #[cfg(unix)]
+            {
+                let reader = zipx_core::reader::ArchiveReader::open(&input, &format)?
+                    .with_password(password.resolve()?);
+                let fs = mount::ArchiveFs::new(reader, max_memory.or(Some(64 << 20)))?;
+                mount::run(fs, &mountpoint).await?;
+            }
+            #[cfg(not(unix))]
+            {
+                let _ = (input, mountpoint, format, password, max_memory);
+                return Err(
+                    "mount is only available on Linux and macOS (FUSE); there is no WinFsp support"
+                        .into(),
+                );
+            }
+        }
+        Commands::List {
+            input,
+            format,
+            password,
+            long,
+            json,
+            dictionary,
+        } => {
+            let reader = zipx_core::reader::ArchiveReader::open(&input, &format)?
+                .with_password(password.resolve()?)
+                .with_dictionary(dictionary.map(std::fs::read).transpose()?);
+            let entries = tokio::task::spawn_blocking(move || reader.entries()).await??;
+            if json {
+                println!("{}", serde_json::to_string_pretty(&entries)?);
+            } else {
+                print_list(&entries, long);
+            }
+        }
+        Commands::Test {
+            input,
+            format,
+            password,
+            dictionary,
+        } => {
+            let report = if is_stdio(&input) {
+                if dictionary.is_some() {
+                    return Err("--dictionary is not supported when reading from stdin".into());
+                }
+                let (detected, reader) =
+                    format_detection::detect_from_reader(tokio::io::stdin()).await?;
+                let format = match (format.as_str(), detected) {
+                    ("auto", DetectedFormat::Unknown) => {
+                        return Err("could not detect the format of stdin; pass --format".into());
+                    }
+                    ("auto", detected) => detected.as_str().to_string(),
+                    _ => format,
+                };
+                extractor
+                    .verify(&format, reader, IntegrityPolicy::default())
+                    .await?
+            } else {
+                let reader = zipx_core::reader::ArchiveReader::open(&input, &format)?
+                    .with_password(password.resolve()?)
+                    .with_dictionary(dictionary.map(std::fs::read).transpose()?);
+                let (line, progress) = ProgressLine::start().unzip();
+                let cancel = cancel_on_ctrl_c();
+                let result = tokio::task::spawn_blocking(move || {
+                    reader.verify(progress.as_ref(), Some(&cancel))
+                })
+                .await?;
+                if let Some(line) = line {
+                    line.finish().await;
+                }
+                result?
+            };
+            println!(
+                "Checked {} files ({})",
+                report.entries,
+                human_size(report.bytes_checked)
+            );
+            if !report.passed {
+                eprintln!("Problems ({}):", report.issues.len());
+                for issue in &report.issues {
+                    match issue.path.as_os_str().is_empty() {
+                        true => eprintln!("- archive: {}", issue.message),
+                        false => eprintln!("- {}: {}", issue.path.display(), issue.message),
+                    }
+                }
+                return Err("archive test failed".into());
+            }
+        }
+        Commands::Du {
+            input,
+            depth,
+            format,
+            bytes,
+        } => {
+            let reader = zipx_core::reader::ArchiveReader::open(&input, &format)?;
+            let entries = tokio::task::spawn_blocking(move || reader.entries()).await??;
+            print_du(&entries, depth, bytes);
+        }
+        Commands::Dict {
+            command:
+                DictCommand::Train {
+                    dir,
+                    output,
+                    max_size,
+                },
+        } => {
+            let max_size = max_size.map_or(codecs::DEFAULT_DICTIONARY_SIZE, |size| size as usize);
+            let dictionary =
+                tokio::task::spawn_blocking(move || codecs::train_dictionary(&dir, max_size))
+                    .await??;
+            std::fs::write(&output, &dictionary)?;
+            println!(
+                "Wrote a {} dictionary to {}",
+                human_size(dictionary.len() as u64),
+                output.display()
+            );
+        }
+        Commands::Doctor => {
+            if !doctor::run(&extractor).await {
+                return Err("self-test failed".into());
+            }
         }
-        Commands::BatchExtract { inputs, output_dir, concurrency } => {
-            if inputs.is_empty() {
+        Commands::BatchExtract {
+            inputs,
+            recursive,
+            scan,
+            manifest,
+            output_dir,
+            concurrency,
+            password,
+            include,
+            exclude,
+            resume,
+            profile,
+            max_memory,
+        } => {
+            let settings = config.settings(profile.as_deref())?;
+            let inputs = expand_inputs(inputs, Some(recursive))?;
+            if inputs.is_empty() && scan.is_none() && manifest.is_none() {
                 eprintln!("Error: No input files specified");
                 return Ok(());
             }
 
-            let mut extract_options = ExtractOptions::default();
-            extract_options.concurrency = concurrency;
-            extract_options.integrity = IntegrityPolicy::strict();
+            let extract_options = ExtractOptions {
+                concurrency: concurrency.or(settings.concurrency).unwrap_or(4),
+                integrity: IntegrityPolicy::strict(),
+                password: password.resolve()?,
+                overwrite: settings.overwrite.unwrap_or(Overwrite::Force).into(),
+                include: include.or_else(|| settings.include.clone()),
+                exclude: exclude.or_else(|| settings.exclude.clone()),
+                resume,
+                max_memory: max_memory.or(settings.max_memory()?),
+                cancel: Some(cancel_on_ctrl_c()),
+                ..Default::default()
+            };
 
             // Create archive list with output directories
-            let archives: Vec<_> = inputs.into_iter().map(|input| {
-                let output = output_dir.join(input.file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy()
-                    .replace(&format!(".{}", input.extension().unwrap_or_default().to_string_lossy()), ""));
-                (input, output)
-            }).collect();
-
-            println!("Batch extracting {} archives...", archives.len());
-            let report = extractor.batch_extract(archives, extract_options).await?;
-
-            println!("Batch extraction complete:");
-            println!("  Total: {}", report.total_archives);
-            println!("  Successful: {}", report.successful);
-            println!("  Failed: {}", report.failed);
-            println!("  Total files extracted: {}", report.total_files);
-            println!("  Total bytes: {}", report.total_bytes);
+            let mut archives: Vec<_> = match &output_dir {
+                Some(output_dir) => inputs
+                    .into_iter()
+                    .map(|input| {
+                        let output = output_dir.join(format_detection::archive_stem(&input));
+                        (input, output)
+                    })
+                    .collect(),
+                None => Vec::new(),
+            };
 
-            if !report.errors.is_empty() {
-                eprintln!("\nErrors ({}):", report.errors.len());
-                for error in &report.errors {
-                    eprintln!("- {}", error);
+            if let Some(root) = scan {
+                let found = format_detection::scan_for_archives(&root, true);
+                println!(
+                    "Scanned {}: {} archives found, {} other files skipped",
+                    root.display(),
+                    found.found.len(),
+                    found.skipped.len()
+                );
+                for (archive, format) in &found.found {
+                    println!("  found {} ({})", archive.display(), format.as_str());
+                    // Mirror the tree under --output-dir, otherwise unpack next to the archive.
+                    let parent = archive.parent().unwrap_or(&root);
+                    let base = match &output_dir {
+                        Some(output_dir) => {
+                            output_dir.join(parent.strip_prefix(&root).unwrap_or(parent))
+                        }
+                        None => parent.to_path_buf(),
+                    };
+                    archives.push((
+                        archive.clone(),
+                        base.join(format_detection::archive_stem(archive)),
+                    ));
                 }
             }
+
+            let mut jobs: Vec<ExtractJob> = archives
+                .into_iter()
+                .map(|(input, output)| ExtractJob {
+                    input,
+                    output,
+                    ..Default::default()
+                })
+                .collect();
+            if let Some(manifest) = &manifest {
+                jobs.extend(manifest::load_extract_jobs(manifest)?);
+            }
+
+            println!("Batch extracting {} archives...", jobs.len());
+            let report = extractor.batch_extract_jobs(jobs, extract_options).await?;
+            print_batch_extract(&report);
         }
-        Commands::BatchCompress { inputs, output_dir, format, level } => {
-            if inputs.is_empty() {
+        Commands::BatchCompress {
+            inputs,
+            manifest,
+            output_dir,
+            format,
+            level,
+            profile,
+            max_memory,
+            concurrency,
+            smallest_first,
+        } => {
+            let settings = config.settings(profile.as_deref())?;
+            let inputs = expand_inputs(inputs, None)?;
+            if inputs.is_empty() && manifest.is_none() {
                 eprintln!("Error: No input files specified");
                 return Ok(());
             }
 
-            let mut compress_options = CompressOptions::default();
-            compress_options.format = format;
-            compress_options.compression_level = level;
+            let compress_options = CompressOptions {
+                format: settings.compress_format(format),
+                compression_level: level.or(settings.level),
+                include: settings.include.clone(),
+                exclude: settings.exclude.clone(),
+                max_memory: max_memory.or(settings.max_memory()?),
+                profile: settings.preset,
+                threads: settings.threads,
+                concurrency: concurrency.or(settings.concurrency).unwrap_or(4),
+                batch_order: if smallest_first {
+                    BatchOrder::SmallestFirst
+                } else {
+                    BatchOrder::AsGiven
+                },
+                cancel: Some(cancel_on_ctrl_c()),
+                ..Default::default()
+            };
 
             // Create source list with output paths
-            let sources: Vec<_> = inputs.into_iter().map(|input| {
-                let output = output_dir.join(format!("{}.{}", input.file_name()
-                    .unwrap_or_default()
-                    .to_string_lossy(), compress_options.format));
-                let format_str = compress_options.format.clone();
-                (input, output, format_str)
-            }).collect();
-
-            println!("Batch compressing {} sources...", sources.len());
-            let report = extractor.batch_compress(sources, compress_options).await?;
+            let mut jobs: Vec<CompressJob> = match &output_dir {
+                Some(output_dir) => inputs
+                    .into_iter()
+                    .map(|input| {
+                        let output = output_dir.join(format!(
+                            "{}.{}",
+                            input.file_name().unwrap_or_default().to_string_lossy(),
+                            compress_options.format
+                        ));
+                        CompressJob {
+                            input,
+                            output,
+                            ..Default::default()
+                        }
+                    })
+                    .collect(),
+                None => Vec::new(),
+            };
+            if let Some(manifest) = &manifest {
+                jobs.extend(manifest::load_compress_jobs(manifest)?);
+            }
 
-            println!("Batch compression complete:");
-            println!("  Total: {}", report.total_sources);
-            println!("  Successful: {}", report.successful);
-            println!("  Failed: {}", report.failed);
-            println!("  Total files processed: {}", report.total_files);
-            println!("  Total bytes read: {}", report.total_bytes_read);
-            println!("  Total bytes written: {}", report.total_bytes_written);
+            println!("Batch compressing {} sources...", jobs.len());
+            let (line, progress) = ProgressLine::start_batch(jobs.len()).unzip();
+            let compress_options = CompressOptions {
+                progress,
+                ..compress_options
+            };
+            let result = extractor.batch_compress_jobs(jobs, compress_options).await;
+            if let Some(line) = line {
+                line.finish().await;
+            }
+            let report = result?;
+            print_batch_compress(&report);
+        }
+        Commands::Batch {
+            manifest,
+            concurrency,
+            password,
+            profile,
+            max_memory,
+        } => {
+            let settings = config.settings(profile.as_deref())?;
+            let jobs = manifest::load_batch_jobs(&manifest)?;
+            let concurrency = concurrency.or(settings.concurrency).unwrap_or(4);
+            let max_memory = max_memory.or(settings.max_memory()?);
+            let cancel = cancel_on_ctrl_c();
+            let extract_options = ExtractOptions {
+                concurrency,
+                integrity: IntegrityPolicy::strict(),
+                password: password.resolve()?,
+                overwrite: settings.overwrite.unwrap_or(Overwrite::Force).into(),
+                include: settings.include.clone(),
+                exclude: settings.exclude.clone(),
+                max_memory,
+                cancel: Some(cancel.clone()),
+                ..Default::default()
+            };
+            let compress_options = CompressOptions {
+                format: settings.compress_format(None),
+                compression_level: settings.level,
+                include: settings.include.clone(),
+                exclude: settings.exclude.clone(),
+                max_memory,
+                profile: settings.preset,
+                threads: settings.threads,
+                concurrency,
+                cancel: Some(cancel),
+                ..Default::default()
+            };
 
-            if !report.errors.is_empty() {
-                eprintln!("\nErrors ({}):", report.errors.len());
-                for error in &report.errors {
-                    eprintln!("- {}", error);
+            println!("Running {} jobs from {}...", jobs.len(), manifest.display());
+            let report = extractor
+                .batch_jobs(jobs, extract_options, compress_options)
+                .await?;
+            if report.extract.total_archives > 0 {
+                print_batch_extract(&report.extract);
+            }
+            if report.compress.total_sources > 0 {
+                print_batch_compress(&report.compress);
+            }
+        }
+        Commands::Backup {
+            input,
+            store,
+            level,
+            include,
+            exclude,
+            dry_run,
+        } => {
+            let options = CompressOptions {
+                sources: input,
+                compression_level: level,
+                include,
+                exclude,
+                dry_run,
+                ..Default::default()
+            };
+            let store = ChunkStore::open(&store)?;
+            let report = tokio::task::spawn_blocking(move || store.backup(&options)).await??;
+            if dry_run {
+                print_plan("back up", &report.planned);
+            } else {
+                println!("Snapshot {}", report.snapshot);
+            }
+            println!(
+                "{} files ({} unchanged), {} read; {} of {} chunks new, {} stored",
+                report.files,
+                report.unchanged,
+                human_size(report.bytes_read),
+                report.new_chunks,
+                report.chunks,
+                human_size(report.bytes_stored)
+            );
+        }
+        Commands::Restore {
+            store,
+            snapshot,
+            output,
+            dry_run,
+            overwrite,
+            strip_components,
+            include,
+            exclude,
+        } => {
+            let options = ExtractOptions {
+                destination: output,
+                dry_run,
+                overwrite: overwrite.unwrap_or(Overwrite::Force).into(),
+                strip_components,
+                include,
+                exclude,
+                ..Default::default()
+            };
+            let store = ChunkStore::open(&store)?;
+            let report =
+                tokio::task::spawn_blocking(move || store.restore(&snapshot, &options)).await??;
+            if dry_run {
+                print_plan("write", &report.planned);
+            } else {
+                println!(
+                    "Restored {} files ({} bytes)",
+                    report.entries, report.bytes_written
+                );
+                if report.skipped > 0 {
+                    println!("Skipped {} existing files", report.skipped);
+                }
+            }
+            for warning in &report.warnings {
+                eprintln!("Warning: {warning}");
+            }
+        }
+        Commands::Delta {
+            input,
+            output,
+            base,
+            save_manifest,
+            format,
+            level,
+            include,
+            exclude,
+            dry_run,
+        } => {
+            let base = base
+                .as_deref()
+                .map(DirManifest::load)
+                .transpose()?
+                .unwrap_or_default();
+            let options = CompressOptions {
+                sources: vec![input],
+                destination: output,
+                format: format.unwrap_or_else(|| "tar.zst".to_string()),
+                compression_level: level,
+                include,
+                exclude,
+                dry_run,
+                ..Default::default()
+            };
+            let report = extractor.create_delta(&base, options).await?;
+            let delta = &report.delta;
+            if dry_run {
+                print_plan("archive", &report.compress.planned);
+            } else {
+                if let Some(path) = &save_manifest {
+                    delta.manifest.save(path)?;
                 }
+                println!("Wrote {} bytes", report.compress.bytes_written);
+            }
+            println!(
+                "{} added, {} changed, {} deleted",
+                delta.added.len(),
+                delta.changed.len(),
+                delta.deleted.len()
+            );
+        }
+        Commands::ApplyDelta {
+            input,
+            output,
+            format,
+            password,
+            dry_run,
+        } => {
+            let format = if format == "auto" {
+                format_detection::detect_format(&input)?
+                    .as_str()
+                    .to_string()
+            } else {
+                format
+            };
+            let options = ExtractOptions {
+                destination: output,
+                password: password.resolve()?,
+                dry_run,
+                ..Default::default()
+            };
+            let applied = extractor.apply_delta(&input, &format, options).await?;
+            if dry_run {
+                print_plan("write", &applied.extract.planned);
+            } else {
+                println!(
+                    "Wrote {} files, deleted {}",
+                    applied.extract.entries, applied.deleted
+                );
+            }
+            for warning in &applied.extract.warnings {
+                eprintln!("Warning: {warning}");
+            }
+        }
+        Commands::Snapshots { store } => {
+            for snapshot in ChunkStore::open(&store)?.snapshots()? {
+                println!(
+                    "{}  {}  {} files  {}",
+                    snapshot.id,
+                    utc_time(snapshot.created),
+                    snapshot.files.len(),
+                    human_size(snapshot.size())
+                );
             }
         }
     }
 
     Ok(())
 }
+
+fn print_plan(action: &str, planned: &[PlannedEntry]) {
+    let total: u64 = planned.iter().map(|p| p.size).sum();
+    for entry in planned {
+        println!(
+            "would {action} {} ({} bytes)",
+            entry.path.display(),
+            entry.size
+        );
+    }
+    println!(
+        "Dry run: {} files, {} bytes; nothing was written",
+        planned.len(),
+        total
+    );
+}
+
+/// [`Extractor::compress_to_writer`], or with `from_stdin` [`Extractor::compress_stream`] of
+/// stdin.
+async fn compress_into<W>(
+    extractor: &Extractor,
+    options: CompressOptions,
+    from_stdin: bool,
+    writer: W,
+) -> Result<CompressReport, ExtractError>
+where
+    W: tokio::io::AsyncWrite + Unpin + Send,
+{
+    match from_stdin {
+        true => {
+            extractor
+                .compress_stream(options, Path::new("-"), std::io::stdin(), writer)
+                .await
+        }
+        false => extractor.compress_to_writer(options, writer).await,
+    }
+}
+
+/// An http(s):// or s3:// archive, for range requests.
+fn open_remote(url: &str) -> Result<HttpSource, Box<dyn std::error::Error>> {
+    if !remote::is_s3(url) {
+        return Ok(HttpSource::open(url)?);
+    }
+    #[cfg(feature = "s3")]
+    return Ok(s3::open(
+        &s3::S3Url::parse(url)?,
+        &s3::S3Config::from_env()?,
+    )?);
+    #[cfg(not(feature = "s3"))]
+    Err(format!("{url}: this build has no S3 support (the `s3` feature)").into())
+}
+
+#[cfg(feature = "s3")]
+async fn upload_to_s3(
+    extractor: &Extractor,
+    options: CompressOptions,
+    from_stdin: bool,
+    url: &str,
+) -> Result<CompressReport, ExtractError> {
+    let (url, config) = (s3::S3Url::parse(url)?, s3::S3Config::from_env()?);
+    s3::upload_with(&url, &config, |writer| {
+        compress_into(extractor, options, from_stdin, writer)
+    })
+    .await
+}
+
+#[cfg(not(feature = "s3"))]
+async fn upload_to_s3(
+    _: &Extractor,
+    _: CompressOptions,
+    _: bool,
+    url: &str,
+) -> Result<CompressReport, ExtractError> {
+    Err(ExtractError::Unsupported(format!(
+        "{url}: this build has no S3 support (the `s3` feature)"
+    )))
+}
+
+fn print_batch_extract(report: &BatchExtractReport) {
+    println!("Batch extraction complete:");
+    println!("  Total: {}", report.total_archives);
+    println!("  Successful: {}", report.successful);
+    println!("  Failed: {}", report.failed);
+    println!("  Total files extracted: {}", report.total_files);
+    println!("  Total bytes: {}", report.total_bytes);
+    print_batch_errors(&report.errors);
+}
+
+fn print_batch_compress(report: &BatchCompressReport) {
+    println!("Batch compression complete:");
+    println!("  Total: {}", report.total_sources);
+    println!("  Successful: {}", report.successful);
+    println!("  Failed: {}", report.failed);
+    println!("  Total files processed: {}", report.total_files);
+    println!("  Total bytes read: {}", report.total_bytes_read);
+    println!("  Total bytes written: {}", report.total_bytes_written);
+    print_batch_errors(&report.errors);
+}
+
+fn print_batch_errors(errors: &[String]) {
+    if !errors.is_empty() {
+        eprintln!("\nErrors ({}):", errors.len());
+        for error in errors {
+            eprintln!("- {}", error);
+        }
+    }
+}
+
+/// The per-file breakdown of a compression run, on stderr when stdout carries the archive.
+fn print_stats(report: &CompressReport, to_stderr: bool) {
+    let mut lines = vec!["Largest files:".to_string()];
+    for entry in &report.largest_files {
+        lines.push(format!(
+            "  {:>10}  {}",
+            human_size(entry.size),
+            entry.path.display()
+        ));
+    }
+    lines.push("By extension:".to_string());
+    for stats in &report.extensions {
+        let extension = match stats.extension.as_str() {
+            "" => "(none)".to_string(),
+            extension => format!(".{extension}"),
+        };
+        lines.push(format!(
+            "  {:>10}  {:>6} files  {extension}",
+            human_size(stats.bytes),
+            stats.files
+        ));
+    }
+    // Dry runs list what they'd archive and don't time anything.
+    let timings = &report.timings;
+    if report.planned.is_empty() {
+        lines.push(format!(
+            "Time: scan {} ms, read {} ms, compress {} ms, write {} ms",
+            timings.scan_ms, timings.read_ms, timings.compress_ms, timings.write_ms
+        ));
+    }
+    for line in lines {
+        if to_stderr {
+            eprintln!("{line}");
+        } else {
+            println!("{line}");
+        }
+    }
+}
+
+/// One line per entry; with `long`, in the style of `tar -tv`.
+fn print_list(entries: &[EntryInfo], long: bool) {
+    for entry in entries {
+        let path = entry.path.display();
+        if !long {
+            println!("{path}");
+            continue;
+        }
+        let kind = match entry.kind {
+            EntryKind::File => '-',
+            EntryKind::Dir => 'd',
+            EntryKind::Symlink => 'l',
+            EntryKind::Other => '?',
+        };
+        let permissions: String = match entry.mode {
+            Some(mode) => (0..9)
+                .rev()
+                .map(|bit| {
+                    if mode >> bit & 1 == 1 {
+                        b"xwr"[bit % 3] as char
+                    } else {
+                        '-'
+                    }
+                })
+                .collect(),
+            None => "?????????".to_string(),
+        };
+        let mtime = entry.mtime.map_or_else(|| "-".to_string(), utc_time);
+        let link = entry
+            .link_target
+            .as_ref()
+            .map(|target| format!(" -> {}", target.display()))
+            .unwrap_or_default();
+        println!(
+            "{kind}{permissions} {:>12} {mtime:>20}  {path}{link}",
+            entry.size
+        );
+    }
+}
+
+/// Uncompressed and, when every entry records it, compressed size per directory down to
+/// `depth` levels, in the style of `du`.
+fn print_du(entries: &[EntryInfo], depth: usize, bytes: bool) {
+    #[derive(Default)]
+    struct Usage {
+        size: u64,
+        compressed: Option<u64>,
+        files: u64,
+    }
+    impl Usage {
+        fn add(&mut self, entry: &EntryInfo) {
+            self.compressed = match (self.files, self.compressed, entry.compressed_size) {
+                (0, _, c) => c,
+                (_, Some(a), Some(b)) => Some(a + b),
+                _ => None,
+            };
+            self.size += entry.size;
+            self.files += 1;
+        }
+    }
+
+    let mut dirs: BTreeMap<PathBuf, Usage> = BTreeMap::new();
+    let mut total = Usage::default();
+    for entry in entries.iter().filter(|e| e.kind != EntryKind::Dir) {
+        total.add(entry);
+        let parents: Vec<_> = entry.path.components().collect();
+        // Every ancestor directory up to `depth`, not the file itself.
+        for len in 1..parents.len().min(depth + 1) {
+            let dir: PathBuf = parents[..len].iter().collect();
+            dirs.entry(dir).or_default().add(entry);
+        }
+    }
+
+    let fmt = |n: u64| if bytes { n.to_string() } else { human_size(n) };
+    let print = |usage: &Usage, name: &str| {
+        let compressed = usage.compressed.map_or_else(|| "-".to_string(), fmt);
+        println!(
+            "{:>12} {:>12} {:>8}  {name}",
+            fmt(usage.size),
+            compressed,
+            usage.files
+        );
+    };
+    println!("{:>12} {:>12} {:>8}  PATH", "SIZE", "COMPRESSED", "FILES");
+    for (dir, usage) in &dirs {
+        print(usage, &format!("{}/", dir.display()));
+    }
+    print(&total, "total");
+}
+
+/// `secs` since the Unix epoch as `YYYY-MM-DD HH:MM UTC`.
+fn utc_time(secs: u64) -> String {
+    // Civil date from days (Howard Hinnant's algorithm).
+    let days = (secs / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let doe = days.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    let time = secs % 86_400;
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02} UTC",
+        time / 3600,
+        time / 60 % 60
+    )
+}
+
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+/// Expand glob patterns ourselves so batch commands behave the same on shells that don't
+/// (Windows cmd). With `dir_search` set, directory inputs are replaced by the archives they
+/// contain, recursively if the flag is true; otherwise directories are kept as-is.
+fn expand_inputs(
+    inputs: Vec<PathBuf>,
+    dir_search: Option<bool>,
+) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut expanded = Vec::new();
+    for input in inputs {
+        let pattern = input.to_string_lossy();
+        let matches: Vec<PathBuf> = if !input.exists() && pattern.contains(['*', '?', '[']) {
+            let matches = glob::glob(&pattern)?.collect::<Result<Vec<_>, _>>()?;
+            if matches.is_empty() {
+                eprintln!("Warning: pattern {pattern} matched nothing");
+            }
+            matches
+        } else {
+            vec![input]
+        };
+        for path in matches {
+            match dir_search {
+                Some(recursive) if path.is_dir() => {
+                    let scan = format_detection::scan_for_archives(&path, recursive);
+                    expanded.extend(scan.found.into_iter().map(|(archive, _)| archive));
+                }
+                _ => expanded.push(path),
+            }
+        }
+    }
+    Ok(expanded)
+}
+
+/// Parse a byte size such as `4096`, `512K`, `512M`, `2G` or `1GiB` (binary units).
+fn parse_size(value: &str) -> Result<u64, String> {
+    let value = value.trim();
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (digits, unit) = value.split_at(split);
+    let number: u64 = digits
+        .parse()
+        .map_err(|_| format!("invalid size: {value}"))?;
+    let shift = match unit.trim().to_ascii_uppercase().as_str() {
+        "" | "B" => 0,
+        "K" | "KB" | "KIB" => 10,
+        "M" | "MB" | "MIB" => 20,
+        "G" | "GB" | "GIB" => 30,
+        "T" | "TB" | "TIB" => 40,
+        _ => return Err(format!("unknown size unit in {value}")),
+    };
+    number
+        .checked_mul(1 << shift)
+        .ok_or_else(|| format!("size too large: {value}"))
+}
+
+/// Parse a compression ratio cap, which has to be a positive number: NaN would never compare
+/// as exceeded, turning the check off.
+fn parse_ratio(value: &str) -> Result<f64, String> {
+    match value.trim().parse::<f64>() {
+        Ok(ratio) if ratio.is_finite() && ratio > 0.0 => Ok(ratio),
+        _ => Err(format!(
+            "invalid ratio: {value} (expected a positive number)"
+        )),
+    }
+}
+
+/// `-` stands for stdin (as an input) or stdout (as an output).
+fn is_stdio(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+/// A token cancelled by the first Ctrl-C, so the core stops between blocks and removes what
+/// it wrote; a second Ctrl-C exits at once.
+fn cancel_on_ctrl_c() -> CancellationToken {
+    let token = CancellationToken::new();
+    let cancel = token.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            cancel.cancel();
+        }
+        if tokio::signal::ctrl_c().await.is_ok() {
+            std::process::exit(130);
+        }
+    });
+    token
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_inputs_expand_globs_and_directories() {
+        let dir = tempfile::tempdir().unwrap();
+        // An empty zip: just the end of central directory record.
+        let zip = [&b"PK\x05\x06"[..], &[0; 18]].concat();
+        for name in ["a.zip", "b.zip", "nested/c.zip"] {
+            std::fs::create_dir_all(dir.path().join(name).parent().unwrap()).unwrap();
+            std::fs::write(dir.path().join(name), &zip).unwrap();
+        }
+        std::fs::write(dir.path().join("notes.txt"), b"notes").unwrap();
+        let top = [dir.path().join("a.zip"), dir.path().join("b.zip")];
+
+        assert_eq!(
+            expand_inputs(vec![dir.path().join("*.zip")], None).unwrap(),
+            top
+        );
+        assert!(expand_inputs(vec![dir.path().join("*.rar")], None)
+            .unwrap()
+            .is_empty());
+
+        let inputs = vec![dir.path().to_path_buf()];
+        assert_eq!(expand_inputs(inputs.clone(), None).unwrap(), inputs);
+        assert_eq!(expand_inputs(inputs.clone(), Some(false)).unwrap(), top);
+        let found = expand_inputs(inputs, Some(true)).unwrap();
+        assert_eq!(
+            found,
+            [
+                top[0].clone(),
+                top[1].clone(),
+                dir.path().join("nested/c.zip")
+            ]
+        );
+
+        // A file really named like a pattern is taken as it is.
+        let other = tempfile::tempdir().unwrap();
+        let named = other.path().join("[x].zip");
+        std::fs::write(&named, &zip).unwrap();
+        assert_eq!(expand_inputs(vec![named.clone()], None).unwrap(), [named]);
+    }
+
+    #[test]
+    fn memory_budgets_take_binary_units() {
+        for (value, bytes) in [
+            ("4096", 4096),
+            ("512K", 512 << 10),
+            ("64 MiB", 64 << 20),
+            ("2g", 2 << 30),
+        ] {
+            assert_eq!(parse_size(value), Ok(bytes), "{value}");
+        }
+        for value in ["", "M", "12Q", "1.5G", "99999999999T"] {
+            assert!(parse_size(value).is_err(), "{value}");
+        }
+    }
+}
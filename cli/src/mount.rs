@@ -0,0 +1,329 @@
+//! `zipx mount`: expose an archive as a read-only FUSE filesystem.
+//!
+//! The tree comes from [`ArchiveReader::entries`]; a file's contents are read with
+//! [`ArchiveReader::read_entry`] when it is opened and kept in a spool (memory, then a temp
+//! file) until it is closed. Tar archives aren't seekable, so each open decodes the stream up
+//! to that entry; zip entries are read directly.
+//!
+//! Only Unix has this: there is no WinFsp backend, so on Windows the command fails with an
+//! error instead.
+
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::{OsStr, OsString};
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use fuser::{
+    FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyData, ReplyDirectory, ReplyEmpty,
+    ReplyEntry, ReplyOpen, Request,
+};
+use zipx_core::containers::spool;
+use zipx_core::reader::{ArchiveReader, EntryInfo, EntryKind};
+
+const TTL: Duration = Duration::from_secs(60);
+const ROOT: u64 = 1;
+
+struct Inode {
+    parent: u64,
+    kind: FileType,
+    entry: Option<EntryInfo>,
+    children: BTreeMap<OsString, u64>,
+}
+
+pub struct ArchiveFs {
+    reader: ArchiveReader,
+    /// Inode `n` lives at index `n - 1`.
+    inodes: Vec<Inode>,
+    handles: HashMap<u64, tempfile::SpooledTempFile>,
+    next_handle: u64,
+    max_memory: Option<u64>,
+    mounted_at: SystemTime,
+    uid: u32,
+    gid: u32,
+}
+
+impl ArchiveFs {
+    pub fn new(reader: ArchiveReader, max_memory: Option<u64>) -> zipx_core::errors::Result<Self> {
+        let mut fs = ArchiveFs {
+            reader,
+            inodes: vec![Inode {
+                parent: ROOT,
+                kind: FileType::Directory,
+                entry: None,
+                children: BTreeMap::new(),
+            }],
+            handles: HashMap::new(),
+            next_handle: 1,
+            max_memory,
+            mounted_at: SystemTime::now(),
+            // SAFETY: getuid/getgid have no preconditions and cannot fail.
+            uid: unsafe { libc::getuid() },
+            gid: unsafe { libc::getgid() },
+        };
+        for entry in fs.reader.entries()? {
+            fs.insert(entry);
+        }
+        Ok(fs)
+    }
+
+    /// Add `entry`, creating any parent directories the archive doesn't list itself.
+    /// Entries whose path would escape the mount are left out.
+    fn insert(&mut self, entry: EntryInfo) {
+        let mut names = Vec::new();
+        for component in entry.path.components() {
+            match component {
+                Component::Normal(name) => names.push(name.to_os_string()),
+                Component::CurDir => {}
+                _ => return,
+            }
+        }
+        let Some(last) = names.pop() else {
+            return;
+        };
+        let mut parent = ROOT;
+        for name in names {
+            parent = self.child_or_insert(parent, name, FileType::Directory);
+        }
+        let kind = match entry.kind {
+            EntryKind::Dir => FileType::Directory,
+            EntryKind::Symlink => FileType::Symlink,
+            EntryKind::File | EntryKind::Other => FileType::RegularFile,
+        };
+        let ino = self.child_or_insert(parent, last, kind);
+        let inode = &mut self.inodes[(ino - 1) as usize];
+        inode.kind = kind;
+        inode.entry = Some(entry);
+    }
+
+    fn child_or_insert(&mut self, parent: u64, name: OsString, kind: FileType) -> u64 {
+        if let Some(&ino) = self.inodes[(parent - 1) as usize].children.get(&name) {
+            return ino;
+        }
+        self.inodes.push(Inode {
+            parent,
+            kind,
+            entry: None,
+            children: BTreeMap::new(),
+        });
+        let ino = self.inodes.len() as u64;
+        self.inodes[(parent - 1) as usize]
+            .children
+            .insert(name, ino);
+        ino
+    }
+
+    fn inode(&self, ino: u64) -> Option<&Inode> {
+        ino.checked_sub(1).and_then(|i| self.inodes.get(i as usize))
+    }
+
+    fn attr(&self, ino: u64, inode: &Inode) -> FileAttr {
+        let entry = inode.entry.as_ref();
+        let size = match (entry, inode.kind) {
+            (Some(e), FileType::Symlink) => e
+                .link_target
+                .as_ref()
+                .map_or(e.size, |t| t.as_os_str().len() as u64),
+            (Some(e), FileType::RegularFile) => e.size,
+            _ => 0,
+        };
+        let mtime = entry.and_then(|e| e.mtime).map_or(self.mounted_at, |secs| {
+            UNIX_EPOCH + Duration::from_secs(secs)
+        });
+        let default_perm = if inode.kind == FileType::Directory {
+            0o755
+        } else {
+            0o644
+        };
+        FileAttr {
+            ino,
+            size,
+            blocks: size.div_ceil(512),
+            atime: mtime,
+            mtime,
+            ctime: mtime,
+            crtime: mtime,
+            kind: inode.kind,
+            // Read-only mount: drop the write bits whatever the archive says.
+            perm: (entry.and_then(|e| e.mode).unwrap_or(default_perm) & 0o555) as u16,
+            nlink: if inode.kind == FileType::Directory {
+                2
+            } else {
+                1
+            },
+            uid: self.uid,
+            gid: self.gid,
+            rdev: 0,
+            blksize: 4096,
+            flags: 0,
+        }
+    }
+
+    fn read_to_spool(&self, path: &Path) -> zipx_core::errors::Result<tempfile::SpooledTempFile> {
+        let mut data = spool(self.max_memory);
+        self.reader.read_entry(path, &mut data)?;
+        data.seek(SeekFrom::Start(0))?;
+        Ok(data)
+    }
+}
+
+impl Filesystem for ArchiveFs {
+    fn lookup(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let found = self
+            .inode(parent)
+            .and_then(|p| p.children.get(name))
+            .copied();
+        match found.and_then(|ino| self.inode(ino).map(|inode| self.attr(ino, inode))) {
+            Some(attr) => reply.entry(&TTL, &attr, 0),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request<'_>, ino: u64, _fh: Option<u64>, reply: ReplyAttr) {
+        match self.inode(ino) {
+            Some(inode) => reply.attr(&TTL, &self.attr(ino, inode)),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn readlink(&mut self, _req: &Request<'_>, ino: u64, reply: ReplyData) {
+        let Some(entry) = self.inode(ino).and_then(|i| i.entry.as_ref()) else {
+            return reply.error(libc::ENOENT);
+        };
+        if let Some(target) = &entry.link_target {
+            return reply.data(target.as_os_str().as_encoded_bytes());
+        }
+        let mut target = Vec::new();
+        match self.reader.read_entry(&entry.path, &mut target) {
+            Ok(_) => reply.data(&target),
+            Err(_) => reply.error(libc::EIO),
+        }
+    }
+
+    fn open(&mut self, _req: &Request<'_>, ino: u64, flags: i32, reply: ReplyOpen) {
+        if flags & libc::O_ACCMODE != libc::O_RDONLY {
+            return reply.error(libc::EROFS);
+        }
+        let Some(inode) = self.inode(ino) else {
+            return reply.error(libc::ENOENT);
+        };
+        let Some(entry) = inode
+            .entry
+            .as_ref()
+            .filter(|_| inode.kind == FileType::RegularFile)
+        else {
+            return reply.error(libc::EISDIR);
+        };
+        match self.read_to_spool(&entry.path.clone()) {
+            Ok(data) => {
+                let fh = self.next_handle;
+                self.next_handle += 1;
+                self.handles.insert(fh, data);
+                reply.opened(fh, 0);
+            }
+            Err(zipx_core::errors::ExtractError::Password) => reply.error(libc::EACCES),
+            Err(_) => reply.error(libc::EIO),
+        }
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let Some(data) = self.handles.get_mut(&fh) else {
+            return reply.error(libc::EBADF);
+        };
+        let mut buf = Vec::with_capacity(size as usize);
+        let result = data
+            .seek(SeekFrom::Start(offset.max(0) as u64))
+            .and_then(|_| data.by_ref().take(u64::from(size)).read_to_end(&mut buf));
+        match result {
+            Ok(_) => reply.data(&buf),
+            Err(_) => reply.error(libc::EIO),
+        }
+    }
+
+    fn release(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        fh: u64,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        _flush: bool,
+        reply: ReplyEmpty,
+    ) {
+        self.handles.remove(&fh);
+        reply.ok();
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let Some(inode) = self.inode(ino) else {
+            return reply.error(libc::ENOENT);
+        };
+        let dots = [
+            (ino, FileType::Directory, OsStr::new(".")),
+            (inode.parent, FileType::Directory, OsStr::new("..")),
+        ];
+        let children = inode.children.iter().map(|(name, &child)| {
+            let kind = self.inode(child).map_or(FileType::RegularFile, |c| c.kind);
+            (child, kind, name.as_os_str())
+        });
+        for (i, (child, kind, name)) in dots
+            .into_iter()
+            .chain(children)
+            .enumerate()
+            .skip(offset.max(0) as usize)
+        {
+            // The offset handed back is where the next call should resume.
+            if reply.add(child, i as i64 + 1, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+}
+
+/// Mount `fs` at `mountpoint` until Ctrl-C or an external unmount (`fusermount -u`).
+pub async fn run(fs: ArchiveFs, mountpoint: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let options = [
+        MountOption::RO,
+        MountOption::FSName("zipx".to_string()),
+        MountOption::Subtype("zipx".to_string()),
+    ];
+    let session = fuser::spawn_mount2(fs, mountpoint, &options)?;
+    eprintln!(
+        "Mounted at {}; press Ctrl-C to unmount",
+        mountpoint.display()
+    );
+    let mut ticker = tokio::time::interval(Duration::from_millis(500));
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    loop {
+        tokio::select! {
+            _ = &mut ctrl_c => break,
+            _ = ticker.tick() => {
+                if session.guard.is_finished() {
+                    break;
+                }
+            }
+        }
+    }
+    // Dropping the session unmounts the filesystem.
+    drop(session);
+    Ok(())
+}
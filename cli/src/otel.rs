@@ -0,0 +1,60 @@
+//! Exporting the core's tracing spans over OTLP, so jobs show up in whatever trace backend the
+//! surrounding service already reports to.
+
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::SpanExporter;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::Resource;
+use tracing::{Level, Subscriber};
+use tracing_subscriber::filter::{Filtered, Targets};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::Layer;
+
+const SERVICE_NAME: &str = "zipx";
+
+/// Flushes buffered spans when dropped.
+pub struct Exporter(SdkTracerProvider);
+
+impl Exporter {
+    /// An exporter when the standard `OTEL_EXPORTER_OTLP_*` variables name an endpoint; the
+    /// exporter reads them itself.
+    pub fn from_env() -> Result<Option<Self>, Box<dyn std::error::Error>> {
+        let configured = [
+            "OTEL_EXPORTER_OTLP_ENDPOINT",
+            "OTEL_EXPORTER_OTLP_TRACES_ENDPOINT",
+        ]
+        .iter()
+        .any(|name| std::env::var_os(name).is_some_and(|value| !value.is_empty()));
+        if !configured {
+            return Ok(None);
+        }
+        let exporter = SpanExporter::builder().with_http().build()?;
+        let provider = SdkTracerProvider::builder()
+            .with_batch_exporter(exporter)
+            .with_resource(Resource::builder().with_service_name(SERVICE_NAME).build())
+            .build();
+        Ok(Some(Exporter(provider)))
+    }
+
+    /// A layer sending this crate's and the core's spans, per-entry ones included, to the
+    /// exporter. Other crates are left out so the exporter's own HTTP client isn't traced.
+    pub fn layer<S>(&self) -> Filtered<impl Layer<S>, Targets, S>
+    where
+        S: Subscriber + for<'a> LookupSpan<'a>,
+    {
+        let targets = Targets::new()
+            .with_target("zipx_core", Level::DEBUG)
+            .with_target("zipx_cli", Level::DEBUG);
+        tracing_opentelemetry::layer()
+            .with_tracer(self.0.tracer(SERVICE_NAME))
+            .with_filter(targets)
+    }
+}
+
+impl Drop for Exporter {
+    fn drop(&mut self) {
+        if let Err(e) = self.0.shutdown() {
+            eprintln!("Warning: could not flush traces: {e}");
+        }
+    }
+}
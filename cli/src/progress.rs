@@ -0,0 +1,138 @@
+//! A live status line on stderr for extract and compress runs started from a terminal.
+
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio::task::JoinHandle;
+use zipx_core::containers::ProgressInfo;
+
+use crate::human_size;
+
+/// Minimum gap between redraws.
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+pub struct ProgressLine {
+    task: JoinHandle<()>,
+}
+
+impl ProgressLine {
+    /// A line drawn from the updates sent to the returned sender, or `None` when stderr isn't
+    /// a terminal, so pipes and logs only get the summary.
+    pub fn start() -> Option<(Self, UnboundedSender<ProgressInfo>)> {
+        if !std::io::stderr().is_terminal() {
+            return None;
+        }
+        let (sender, mut receiver) = unbounded_channel::<ProgressInfo>();
+        let task = tokio::spawn(async move {
+            let mut last_draw: Option<Instant> = None;
+            while let Some(info) = receiver.recv().await {
+                if last_draw.is_some_and(|at| at.elapsed() < REDRAW_INTERVAL) {
+                    continue;
+                }
+                last_draw = Some(Instant::now());
+                draw(&info, "");
+            }
+        });
+        Some((ProgressLine { task }, sender))
+    }
+
+    /// Like [`ProgressLine::start`] for a batch of `total_archives` run side by side: the
+    /// line sums the archives' updates and counts the ones finished.
+    pub fn start_batch(total_archives: usize) -> Option<(Self, UnboundedSender<ProgressInfo>)> {
+        if !std::io::stderr().is_terminal() {
+            return None;
+        }
+        let (sender, mut receiver) = unbounded_channel::<ProgressInfo>();
+        let task = tokio::spawn(async move {
+            let mut latest = vec![ProgressInfo::default(); total_archives];
+            let mut done = vec![false; total_archives];
+            let mut last_draw: Option<Instant> = None;
+            while let Some(info) = receiver.recv().await {
+                let Some(index) = info.archive.filter(|&i| i < total_archives) else {
+                    continue;
+                };
+                let current_file = match info.archive_done {
+                    true => {
+                        done[index] = true;
+                        String::new()
+                    }
+                    false => {
+                        let current_file = info.current_file.clone();
+                        latest[index] = info;
+                        current_file
+                    }
+                };
+                if last_draw.is_some_and(|at| at.elapsed() < REDRAW_INTERVAL) {
+                    continue;
+                }
+                last_draw = Some(Instant::now());
+                let total = ProgressInfo {
+                    current_file,
+                    bytes_processed: latest.iter().map(|i| i.bytes_processed).sum(),
+                    files_processed: latest.iter().map(|i| i.files_processed).sum(),
+                    rate: latest
+                        .iter()
+                        .zip(&done)
+                        .filter(|(_, &d)| !d)
+                        .map(|(i, _)| i.rate)
+                        .sum(),
+                    ..Default::default()
+                };
+                let finished = done.iter().filter(|&&d| d).count();
+                draw(&total, &format!("[{finished}/{total_archives}] "));
+            }
+        });
+        Some((ProgressLine { task }, sender))
+    }
+
+    /// Stop drawing and clear the line, so the summary or error starts on a clean one.
+    pub async fn finish(self) {
+        self.task.abort();
+        let _ = self.task.await;
+        eprint!("\r\x1b[2K");
+        let _ = std::io::stderr().flush();
+    }
+}
+
+fn draw(info: &ProgressInfo, prefix: &str) {
+    let mut line = prefix.to_string();
+    line += &match info.total_bytes {
+        // Tar archives don't say how much they hold until the end.
+        0 => human_size(info.bytes_processed),
+        total => format!(
+            "{:>3}% {} of {}",
+            info.bytes_processed.min(total) * 100 / total,
+            human_size(info.bytes_processed),
+            human_size(total)
+        ),
+    };
+    match info.total_files {
+        0 => line.push_str(&format!("  {} files", info.files_processed)),
+        total => line.push_str(&format!("  {}/{total} files", info.files_processed)),
+    }
+    if info.rate > 0.0 {
+        line.push_str(&format!("  {}/s", human_size(info.rate as u64)));
+    }
+    if let Some(eta) = info.eta_secs {
+        line.push_str(&format!("  ETA {}", clock(eta as u64)));
+    }
+    line.push_str("  ");
+    line.push_str(&info.current_file);
+
+    let width = match ratatui::crossterm::terminal::size() {
+        Ok((columns, _)) if columns > 0 => columns as usize,
+        _ => 80,
+    };
+    let line: String = line.chars().take(width.saturating_sub(1)).collect();
+    eprint!("\r\x1b[2K{line}");
+    let _ = std::io::stderr().flush();
+}
+
+/// `secs` as `m:ss`, or `h:mm:ss` from an hour up.
+fn clock(secs: u64) -> String {
+    match secs {
+        0..3600 => format!("{}:{:02}", secs / 60, secs % 60),
+        _ => format!("{}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60),
+    }
+}
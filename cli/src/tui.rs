@@ -0,0 +1,458 @@
+//! `zipx tui`: browse an archive's tree, mark entries and extract the selection while a
+//! progress pane follows along. Works over plain SSH where the desktop app isn't available.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Style, Stylize};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Gauge, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver};
+use tokio::task::JoinHandle;
+use zipx_core::containers::{ExtractOptions, ExtractReport, PlannedEntry, ProgressInfo};
+use zipx_core::errors::Result as ExtractResult;
+use zipx_core::pipeline::Extractor;
+
+use crate::human_size;
+
+type Error = Box<dyn std::error::Error>;
+
+struct Node {
+    name: String,
+    path: PathBuf,
+    size: u64,
+    is_dir: bool,
+    expanded: bool,
+    marked: bool,
+    parent: usize,
+    children: BTreeMap<String, usize>,
+}
+
+/// The archive contents as a tree; node 0 is the (unnamed) root and every node comes after
+/// its parent in `nodes`.
+struct Tree {
+    nodes: Vec<Node>,
+}
+
+impl Tree {
+    fn build(files: &[PlannedEntry]) -> Self {
+        let mut tree = Tree {
+            nodes: vec![Tree::node(String::new(), PathBuf::new(), true, 0)],
+        };
+        tree.nodes[0].expanded = true;
+        for file in files {
+            let mut current = 0;
+            let mut path = PathBuf::new();
+            let components: Vec<_> = file.path.components().collect();
+            for (i, component) in components.iter().enumerate() {
+                let name = component.as_os_str().to_string_lossy().into_owned();
+                path.push(component);
+                let is_dir = i + 1 < components.len();
+                current = match tree.nodes[current].children.get(&name) {
+                    Some(&child) => child,
+                    None => {
+                        let child = tree.nodes.len();
+                        tree.nodes
+                            .push(Tree::node(name.clone(), path.clone(), is_dir, current));
+                        tree.nodes[current].children.insert(name, child);
+                        child
+                    }
+                };
+                tree.nodes[current].size += file.size;
+            }
+        }
+        tree
+    }
+
+    fn node(name: String, path: PathBuf, is_dir: bool, parent: usize) -> Node {
+        Node {
+            name,
+            path,
+            size: 0,
+            is_dir,
+            expanded: false,
+            marked: false,
+            parent,
+            children: BTreeMap::new(),
+        }
+    }
+
+    /// Directories first, then files, each group in name order.
+    fn sorted_children(&self, idx: usize) -> Vec<usize> {
+        let mut children: Vec<usize> = self.nodes[idx].children.values().copied().collect();
+        children.sort_by_key(|&c| !self.nodes[c].is_dir);
+        children
+    }
+
+    /// Rows currently on screen as `(node, depth)`, following expanded directories.
+    fn visible(&self) -> Vec<(usize, usize)> {
+        let mut rows = Vec::new();
+        let mut stack: Vec<(usize, usize)> = self
+            .sorted_children(0)
+            .into_iter()
+            .rev()
+            .map(|c| (c, 0))
+            .collect();
+        while let Some((idx, depth)) = stack.pop() {
+            rows.push((idx, depth));
+            if self.nodes[idx].is_dir && self.nodes[idx].expanded {
+                stack.extend(
+                    self.sorted_children(idx)
+                        .into_iter()
+                        .rev()
+                        .map(|c| (c, depth + 1)),
+                );
+            }
+        }
+        rows
+    }
+
+    fn set_marked(&mut self, idx: usize, marked: bool) {
+        let mut stack = vec![idx];
+        while let Some(idx) = stack.pop() {
+            self.nodes[idx].marked = marked;
+            stack.extend(self.nodes[idx].children.values().copied());
+        }
+    }
+
+    /// `(marked, total)` file counts below every node, indexed like `nodes`.
+    fn mark_counts(&self) -> Vec<(usize, usize)> {
+        let mut counts = vec![(0, 0); self.nodes.len()];
+        for (idx, node) in self.nodes.iter().enumerate().rev() {
+            if !node.is_dir {
+                counts[idx] = (usize::from(node.marked), 1);
+            }
+            if idx > 0 {
+                let (m, t) = counts[idx];
+                counts[node.parent].0 += m;
+                counts[node.parent].1 += t;
+            }
+        }
+        counts
+    }
+
+    fn marked_files(&self) -> Vec<&Node> {
+        self.nodes
+            .iter()
+            .filter(|n| !n.is_dir && n.marked)
+            .collect()
+    }
+}
+
+struct Job {
+    handle: JoinHandle<ExtractResult<ExtractReport>>,
+    progress: UnboundedReceiver<ProgressInfo>,
+    files: usize,
+    bytes: u64,
+}
+
+struct App {
+    extractor: Extractor,
+    archive: PathBuf,
+    format: String,
+    destination: PathBuf,
+    password: Option<String>,
+    tree: Tree,
+    list: ListState,
+    job: Option<Job>,
+    latest: Option<ProgressInfo>,
+    status: String,
+}
+
+pub async fn run(
+    extractor: Extractor,
+    archive: PathBuf,
+    format: String,
+    destination: PathBuf,
+    password: Option<String>,
+) -> Result<(), Error> {
+    // A dry run lists the archive without writing anything.
+    let listing = extractor
+        .extract(
+            &format,
+            open(&archive).await?,
+            ExtractOptions {
+                destination: PathBuf::new(),
+                dry_run: true,
+                password: password.clone(),
+                ..Default::default()
+            },
+        )
+        .await?;
+    let mut app = App {
+        extractor,
+        archive,
+        format,
+        destination,
+        password,
+        tree: Tree::build(&listing.planned),
+        list: ListState::default().with_selected(Some(0)),
+        job: None,
+        latest: None,
+        status: format!("{} files", listing.planned.len()),
+    };
+
+    let mut terminal = ratatui::init();
+    let result = app.event_loop(&mut terminal).await;
+    ratatui::restore();
+    result
+}
+
+async fn open(path: &Path) -> Result<tokio::io::BufReader<tokio::fs::File>, Error> {
+    Ok(tokio::io::BufReader::new(
+        tokio::fs::File::open(path).await?,
+    ))
+}
+
+impl App {
+    async fn event_loop(&mut self, terminal: &mut DefaultTerminal) -> Result<(), Error> {
+        loop {
+            self.poll_job().await;
+            terminal.draw(|frame| self.draw(frame))?;
+            if !event::poll(Duration::from_millis(100))? {
+                continue;
+            }
+            let Event::Key(key) = event::read()? else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            let rows = self.tree.visible();
+            let selected = self
+                .list
+                .selected()
+                .and_then(|i| rows.get(i))
+                .map(|&(idx, _)| idx);
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => {
+                    if let Some(job) = self.job.take() {
+                        job.handle.abort();
+                    }
+                    return Ok(());
+                }
+                KeyCode::Down | KeyCode::Char('j') => self.list.select_next(),
+                KeyCode::Up | KeyCode::Char('k') => self.list.select_previous(),
+                KeyCode::Home | KeyCode::Char('g') => self.list.select_first(),
+                KeyCode::End | KeyCode::Char('G') => self.list.select_last(),
+                KeyCode::Right | KeyCode::Char('l') | KeyCode::Enter => {
+                    if let Some(idx) = selected {
+                        self.tree.nodes[idx].expanded = true;
+                    }
+                }
+                KeyCode::Left | KeyCode::Char('h') => {
+                    if let Some(idx) = selected {
+                        self.tree.nodes[idx].expanded = false;
+                    }
+                }
+                KeyCode::Char(' ') => {
+                    if let Some(idx) = selected {
+                        let (marked, total) = self.tree.mark_counts()[idx];
+                        self.tree.set_marked(idx, marked < total);
+                        self.list.select_next();
+                    }
+                }
+                KeyCode::Char('a') => {
+                    let (marked, total) = self.tree.mark_counts()[0];
+                    self.tree.set_marked(0, marked < total);
+                }
+                KeyCode::Char('x') if self.job.is_none() => self.start_extraction(),
+                _ => {}
+            }
+        }
+    }
+
+    fn start_extraction(&mut self) {
+        let marked = self.tree.marked_files();
+        if marked.is_empty() {
+            self.status = "Nothing marked; press space to mark entries".to_string();
+            return;
+        }
+        let include: Vec<String> = marked
+            .iter()
+            .map(|n| globset::escape(&n.path.to_string_lossy()))
+            .collect();
+        let (files, bytes) = (marked.len(), marked.iter().map(|n| n.size).sum());
+        let (sender, progress) = unbounded_channel();
+        let options = ExtractOptions {
+            destination: self.destination.clone(),
+            password: self.password.clone(),
+            include: Some(include),
+            progress: Some(sender),
+            ..Default::default()
+        };
+        let (extractor, archive, format) = (
+            self.extractor.clone(),
+            self.archive.clone(),
+            self.format.clone(),
+        );
+        let handle = tokio::spawn(async move {
+            let reader = tokio::io::BufReader::new(tokio::fs::File::open(&archive).await?);
+            extractor.extract(&format, reader, options).await
+        });
+        self.latest = None;
+        self.status = format!("Extracting {files} files to {}", self.destination.display());
+        self.job = Some(Job {
+            handle,
+            progress,
+            files,
+            bytes,
+        });
+    }
+
+    async fn poll_job(&mut self) {
+        let Some(job) = self.job.as_mut() else {
+            return;
+        };
+        while let Ok(info) = job.progress.try_recv() {
+            self.latest = Some(info);
+        }
+        if !job.handle.is_finished() {
+            return;
+        }
+        let job = self.job.take().expect("job checked above");
+        self.status = match job.handle.await {
+            Ok(Ok(report)) if report.warnings.is_empty() => {
+                format!(
+                    "Extracted {} files ({}) to {}",
+                    report.entries,
+                    human_size(report.bytes_written),
+                    self.destination.display()
+                )
+            }
+            Ok(Ok(report)) => format!(
+                "Extracted {} files with {} warnings; first: {}",
+                report.entries,
+                report.warnings.len(),
+                report.warnings[0]
+            ),
+            Ok(Err(e)) => format!("Extraction failed: {e}"),
+            Err(e) => format!("Extraction task failed: {e}"),
+        };
+        self.latest = Some(ProgressInfo {
+            files_processed: job.files as u64,
+            bytes_processed: job.bytes,
+            ..self.latest.take().unwrap_or_default()
+        });
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [header, body, progress, help] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Min(3),
+            Constraint::Length(4),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let counts = self.tree.mark_counts();
+        let (marked, total) = counts[0];
+        let marked_bytes: u64 = self.tree.marked_files().iter().map(|n| n.size).sum();
+        frame.render_widget(
+            Paragraph::new(format!(
+                "{} ({})  marked {marked}/{total} files, {}  -> {}",
+                self.archive.display(),
+                self.format,
+                human_size(marked_bytes),
+                self.destination.display()
+            )),
+            header,
+        );
+
+        let items: Vec<ListItem> = self
+            .tree
+            .visible()
+            .into_iter()
+            .map(|(idx, depth)| {
+                let node = &self.tree.nodes[idx];
+                let (m, t) = counts[idx];
+                let check = if m == 0 {
+                    "[ ]"
+                } else if m == t {
+                    "[x]"
+                } else {
+                    "[-]"
+                };
+                let arrow = match (node.is_dir, node.expanded) {
+                    (false, _) => " ",
+                    (true, false) => "▸",
+                    (true, true) => "▾",
+                };
+                let suffix = if node.is_dir { "/" } else { "" };
+                ListItem::new(format!(
+                    "{check} {}{arrow} {}{suffix}  {}",
+                    "  ".repeat(depth),
+                    node.name,
+                    human_size(node.size)
+                ))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(Block::bordered().title(" Entries "))
+            .highlight_style(Style::new().reversed());
+        frame.render_stateful_widget(list, body, &mut self.list);
+
+        let pane = Block::bordered().title(" Progress ");
+        let inner = pane.inner(progress);
+        frame.render_widget(pane, progress);
+        let [gauge_area, status_area] =
+            Layout::vertical([Constraint::Length(1), Constraint::Length(1)]).areas(inner);
+        let (ratio, label) = match (&self.job, &self.latest) {
+            (Some(job), Some(info)) => (
+                if job.bytes == 0 {
+                    0.0
+                } else {
+                    (info.bytes_processed as f64 / job.bytes as f64).min(1.0)
+                },
+                format!(
+                    "{}/{} files  {}/s{}  {}",
+                    info.files_processed,
+                    job.files,
+                    human_size(info.rate as u64),
+                    eta_label(job.bytes.saturating_sub(info.bytes_processed), info.rate),
+                    info.current_file
+                ),
+            ),
+            (Some(job), None) => (0.0, format!("0/{} files", job.files)),
+            (None, Some(info)) => (
+                1.0,
+                format!(
+                    "{} files, {}",
+                    info.files_processed,
+                    human_size(info.bytes_processed)
+                ),
+            ),
+            (None, None) => (0.0, String::new()),
+        };
+        frame.render_widget(Gauge::default().ratio(ratio).label(label), gauge_area);
+        frame.render_widget(Paragraph::new(self.status.as_str()), status_area);
+
+        frame.render_widget(
+            Paragraph::new(
+                Line::from(
+                    "↑↓ move  → open  ← close  space mark  a mark all  x extract marked  q quit",
+                )
+                .dim(),
+            ),
+            help,
+        );
+    }
+}
+
+/// "  2m 5s left" for `remaining` bytes at `rate` bytes per second, or nothing while the rate
+/// isn't known yet. The core's own ETA counts the whole archive, not just the marked files.
+fn eta_label(remaining: u64, rate: f64) -> String {
+    if rate <= 0.0 {
+        return String::new();
+    }
+    let secs = (remaining as f64 / rate).ceil() as u64;
+    match secs {
+        0..=59 => format!("  {secs}s left"),
+        60..=3599 => format!("  {}m {}s left", secs / 60, secs % 60),
+        _ => format!("  {}h {}m left", secs / 3600, secs % 3600 / 60),
+    }
+}
@@ -0,0 +1,276 @@
+//! Archive browsing for the frontend: entry listings shaped as a directory tree, size
+//! estimates and in-memory previews of single entries.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::{Component, Path};
+
+use base64::Engine;
+use serde::Serialize;
+use zipx_core::errors::{ExtractError, Result};
+use zipx_core::reader::{ArchiveReader, EntryInfo, EntryKind};
+
+use crate::errors::{CommandError, ErrorKind};
+
+/// Most bytes of an entry `preview` will load, whatever the caller asks for.
+pub const PREVIEW_LIMIT: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ArchiveNode {
+    pub name: String,
+    /// Path inside the archive, `/`-separated, as accepted by the entry commands.
+    pub path: String,
+    /// For directories, the total size of everything below them.
+    pub size: u64,
+    pub mtime: Option<u64>,
+    pub is_dir: bool,
+    pub encrypted: bool,
+    /// Sorted by name, directories first.
+    pub children: Vec<ArchiveNode>,
+}
+
+#[derive(Default)]
+struct Branch {
+    node: ArchiveNode,
+    children: BTreeMap<String, Branch>,
+}
+
+/// Top-level nodes of the tree described by `entries`. Directories the archive doesn't list
+/// itself are filled in; entries whose path would escape the archive are left out.
+pub fn tree(entries: Vec<EntryInfo>) -> Vec<ArchiveNode> {
+    let mut root = Branch::default();
+    for entry in entries {
+        let mut names = Vec::new();
+        for component in entry.path.components() {
+            match component {
+                Component::Normal(name) => names.push(name.to_string_lossy().into_owned()),
+                Component::CurDir => {}
+                _ => {
+                    names.clear();
+                    break;
+                }
+            }
+        }
+        if names.is_empty() {
+            continue;
+        }
+
+        let mut branch = &mut root;
+        let mut path = String::new();
+        for name in names {
+            if !path.is_empty() {
+                path.push('/');
+            }
+            path.push_str(&name);
+            branch = branch
+                .children
+                .entry(name.clone())
+                .or_insert_with(|| Branch {
+                    node: ArchiveNode {
+                        name,
+                        path: path.clone(),
+                        is_dir: true,
+                        ..Default::default()
+                    },
+                    children: BTreeMap::new(),
+                });
+        }
+        let node = &mut branch.node;
+        node.is_dir = entry.kind == EntryKind::Dir;
+        node.mtime = entry.mtime;
+        node.encrypted = entry.encrypted;
+        if !node.is_dir {
+            node.size = entry.size;
+        }
+    }
+    finish(root).children
+}
+
+fn finish(branch: Branch) -> ArchiveNode {
+    let mut node = branch.node;
+    node.children = branch.children.into_values().map(finish).collect();
+    node.children.sort_by_key(|child| !child.is_dir);
+    if node.is_dir {
+        node.size = node.children.iter().map(|child| child.size).sum();
+    }
+    node
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct Estimate {
+    pub entries: u64,
+    pub files: u64,
+    /// Bytes extraction would write.
+    pub total_size: u64,
+    /// Size of the archive itself.
+    pub archive_size: u64,
+    /// Whether any entry needs a password.
+    pub encrypted: bool,
+}
+
+/// What extracting everything in `entries` would take.
+pub fn estimate(entries: &[EntryInfo], archive_size: u64) -> Estimate {
+    let mut estimate = Estimate {
+        entries: entries.len() as u64,
+        archive_size,
+        ..Default::default()
+    };
+    for entry in entries {
+        if entry.kind == EntryKind::File {
+            estimate.files += 1;
+            estimate.total_size += entry.size;
+        }
+        estimate.encrypted |= entry.encrypted;
+    }
+    estimate
+}
+
+/// Allowance per file for filesystem block rounding when checking free space.
+const BLOCK_ALLOWANCE: u64 = 4096;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SpaceCheck {
+    /// Bytes extraction is expected to take, allowing for block rounding.
+    pub required: u64,
+    /// Free bytes on the destination's volume.
+    pub available: u64,
+    /// A `disk_full` error to show as a confirmation when `available` falls short.
+    pub warning: Option<CommandError>,
+}
+
+/// Compare what extracting `estimate` would write with the free space where `destination` is,
+/// or would be once created.
+pub fn check_space(estimate: &Estimate, destination: &Path) -> std::io::Result<SpaceCheck> {
+    let volume = destination
+        .ancestors()
+        .find(|dir| dir.exists())
+        .unwrap_or(Path::new("."));
+    let available = fs2::available_space(volume)?;
+    let required = estimate.total_size + estimate.files * BLOCK_ALLOWANCE;
+    let warning = (available < required).then(|| {
+        let message = format!(
+            "extracting needs about {} but only {} is free on {}",
+            human_size(required),
+            human_size(available),
+            volume.display()
+        );
+        CommandError::new(ErrorKind::DiskFull, message).with_path(volume)
+    });
+    Ok(SpaceCheck {
+        required,
+        available,
+        warning,
+    })
+}
+
+fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KiB", "MiB", "GiB", "TiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Preview {
+    pub path: String,
+    /// Best-effort guess from the contents, then the extension.
+    pub mime: String,
+    /// Base64 of the first `max_bytes` of the entry.
+    pub data: String,
+    /// Whether the entry is longer than what `data` holds.
+    pub truncated: bool,
+}
+
+/// Keeps the first `limit` bytes, then fails so the copy feeding it stops early.
+struct Capped {
+    data: Vec<u8>,
+    limit: usize,
+    truncated: bool,
+}
+
+impl Write for Capped {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let room = self.limit - self.data.len();
+        if room == 0 && !buf.is_empty() {
+            self.truncated = true;
+            return Err(std::io::Error::other("preview limit reached"));
+        }
+        let taken = buf.len().min(room);
+        self.data.extend_from_slice(&buf[..taken]);
+        Ok(taken)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The first `limit` bytes of the entry at `path`, and whether it is longer than that.
+pub fn read_capped(reader: &ArchiveReader, path: &str, limit: usize) -> Result<(Vec<u8>, bool)> {
+    let mut out = Capped {
+        data: Vec::new(),
+        limit,
+        truncated: false,
+    };
+    match reader.read_entry(Path::new(path), &mut out) {
+        Ok(_) => {}
+        Err(ExtractError::Io(_)) if out.truncated => {}
+        Err(e) => return Err(e),
+    }
+    Ok((out.data, out.truncated))
+}
+
+/// Read up to `max_bytes` (capped at [`PREVIEW_LIMIT`]) of the entry at `path`.
+pub fn preview(reader: &ArchiveReader, path: &str, max_bytes: Option<usize>) -> Result<Preview> {
+    let limit = max_bytes.unwrap_or(PREVIEW_LIMIT).min(PREVIEW_LIMIT);
+    let (data, truncated) = read_capped(reader, path, limit)?;
+    Ok(Preview {
+        path: path.to_string(),
+        mime: mime_type(path, &data),
+        data: base64::engine::general_purpose::STANDARD.encode(&data),
+        truncated,
+    })
+}
+
+fn mime_type(path: &str, data: &[u8]) -> String {
+    if let Some(kind) = infer::get(data) {
+        return kind.mime_type().to_string();
+    }
+    let extension = Path::new(path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let by_extension = match extension.as_str() {
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "svg" => "image/svg+xml",
+        "html" | "htm" => "text/html",
+        "css" => "text/css",
+        "csv" => "text/csv",
+        "md" | "markdown" => "text/markdown",
+        "js" | "mjs" => "text/javascript",
+        _ => "",
+    };
+    if !by_extension.is_empty() {
+        return by_extension.to_string();
+    }
+    // A cut-off multi-byte character at the end still counts as text.
+    let text = match std::str::from_utf8(data) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    };
+    if text {
+        "text/plain"
+    } else {
+        "application/octet-stream"
+    }
+    .to_string()
+}
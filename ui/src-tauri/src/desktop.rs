@@ -0,0 +1,44 @@
+//! Handing paths to the platform file manager.
+
+use std::path::Path;
+use std::process::Command;
+
+/// Open `path` with its default application; folders open in the file manager.
+pub fn open(path: &Path) -> Result<(), String> {
+    let mut command = if cfg!(target_os = "windows") {
+        Command::new("explorer")
+    } else if cfg!(target_os = "macos") {
+        Command::new("open")
+    } else {
+        Command::new("xdg-open")
+    };
+    command.arg(path);
+    spawn(command)
+}
+
+/// Show `path` selected in its folder. Linux file managers have no common way to select a
+/// file, so its folder is opened instead.
+pub fn reveal(path: &Path) -> Result<(), String> {
+    if cfg!(target_os = "windows") {
+        let mut command = Command::new("explorer");
+        command.arg(format!("/select,{}", path.display()));
+        spawn(command)
+    } else if cfg!(target_os = "macos") {
+        let mut command = Command::new("open");
+        command.arg("-R").arg(path);
+        spawn(command)
+    } else {
+        open(
+            path.parent()
+                .filter(|p| !p.as_os_str().is_empty())
+                .unwrap_or(Path::new(".")),
+        )
+    }
+}
+
+fn spawn(mut command: Command) -> Result<(), String> {
+    let mut child = command.spawn().map_err(|e| e.to_string())?;
+    // Reap the launcher in the background rather than leave a zombie behind.
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
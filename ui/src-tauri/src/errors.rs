@@ -0,0 +1,177 @@
+//! The error object commands return, so the frontend can tell "password required" from "disk
+//! full" without parsing messages.
+
+use std::io::ErrorKind as IoKind;
+use std::path::Path;
+
+use serde::Serialize;
+use zipx_core::errors::ExtractError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    /// The archive is encrypted and the password is missing or wrong.
+    PasswordRequired,
+    DiskFull,
+    PermissionDenied,
+    NotFound,
+    AlreadyExists,
+    Unsupported,
+    /// The archive is damaged or not what its name says.
+    Corrupt,
+    /// A bad argument, such as an invalid pattern or mismatched passwords.
+    InvalidInput,
+    Cancelled,
+    Io,
+    Internal,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandError {
+    pub kind: ErrorKind,
+    pub message: String,
+    /// The file or archive entry the error is about, when known.
+    pub path: Option<String>,
+    /// Byte offset into the archive, for corruption.
+    pub offset: Option<u64>,
+    /// Whether the same request can succeed once the user acts on `suggestion`.
+    pub retryable: bool,
+    pub suggestion: Option<String>,
+}
+
+impl CommandError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        let (retryable, suggestion) = match kind {
+            ErrorKind::PasswordRequired => {
+                (true, Some("Enter the archive's password and try again."))
+            }
+            ErrorKind::DiskFull => (
+                true,
+                Some("Free up space on the destination drive or choose another destination."),
+            ),
+            ErrorKind::PermissionDenied => (
+                true,
+                Some("Choose a location you can write to, or check the file's permissions."),
+            ),
+            ErrorKind::NotFound => (
+                false,
+                Some("Check that the file still exists and hasn't been moved."),
+            ),
+            ErrorKind::AlreadyExists => (
+                true,
+                Some("Choose another destination or change the overwrite setting."),
+            ),
+            ErrorKind::Unsupported => (false, None),
+            ErrorKind::Corrupt => (
+                false,
+                Some("The archive looks damaged; try downloading it again."),
+            ),
+            ErrorKind::InvalidInput => (false, None),
+            ErrorKind::Cancelled | ErrorKind::Io => (true, None),
+            ErrorKind::Internal => (false, None),
+        };
+        CommandError {
+            kind,
+            message: message.into(),
+            path: None,
+            offset: None,
+            retryable,
+            suggestion: suggestion.map(str::to_string),
+        }
+    }
+
+    pub fn with_path(mut self, path: &Path) -> Self {
+        self.path = Some(path.to_string_lossy().into_owned());
+        self
+    }
+
+    pub fn invalid(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::InvalidInput, message)
+    }
+
+    pub fn unsupported(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::Unsupported, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorKind::NotFound, message)
+    }
+}
+
+/// Disk-full errors by OS code, for platforms the std error kind doesn't cover.
+fn is_disk_full(error: &std::io::Error) -> bool {
+    matches!(error.kind(), IoKind::StorageFull | IoKind::QuotaExceeded)
+        || match error.raw_os_error() {
+            Some(code) if cfg!(windows) => code == 39 || code == 112,
+            Some(code) => code == 28,
+            None => false,
+        }
+}
+
+impl From<std::io::Error> for CommandError {
+    fn from(error: std::io::Error) -> Self {
+        let kind = match error.kind() {
+            _ if is_disk_full(&error) => ErrorKind::DiskFull,
+            IoKind::NotFound => ErrorKind::NotFound,
+            IoKind::PermissionDenied | IoKind::ReadOnlyFilesystem => ErrorKind::PermissionDenied,
+            IoKind::AlreadyExists => ErrorKind::AlreadyExists,
+            IoKind::InvalidData | IoKind::UnexpectedEof => ErrorKind::Corrupt,
+            _ => ErrorKind::Io,
+        };
+        Self::new(kind, error.to_string())
+    }
+}
+
+impl From<ExtractError> for CommandError {
+    fn from(error: ExtractError) -> Self {
+        let message = error.to_string();
+        match error {
+            ExtractError::Io(error) => Self {
+                message,
+                ..error.into()
+            },
+            ExtractError::Password => Self::new(ErrorKind::PasswordRequired, message),
+            ExtractError::Cancelled => Self::new(ErrorKind::Cancelled, message),
+            ExtractError::Unsupported(_) | ExtractError::Unimplemented(_) => {
+                Self::unsupported(message)
+            }
+            ExtractError::CorruptBlock { offset } => Self {
+                offset: Some(offset),
+                ..Self::new(ErrorKind::Corrupt, message)
+            },
+            // Core reports some failed writes this way, with the io error's text as details.
+            ExtractError::IntegrityFailure { details }
+                if details.contains("No space left") || details.contains("not enough space") =>
+            {
+                Self::new(ErrorKind::DiskFull, message)
+            }
+            ExtractError::IntegrityFailure { .. } => Self::new(ErrorKind::Corrupt, message),
+            ExtractError::AlreadyExists { path } => {
+                Self::new(ErrorKind::AlreadyExists, message).with_path(&path)
+            }
+            ExtractError::EntryNotFound(path) => Self::not_found(message).with_path(&path),
+            ExtractError::PathEscape { path } => {
+                Self::new(ErrorKind::Corrupt, message).with_path(&path)
+            }
+            ExtractError::LimitExceeded(_) => Self::new(ErrorKind::Corrupt, message),
+            ExtractError::LinkRejected { path } => Self::unsupported(message).with_path(&path),
+            ExtractError::Pattern(_) | ExtractError::Manifest(_) => Self::invalid(message),
+            ExtractError::Serde(_) | ExtractError::Join(_) => {
+                Self::new(ErrorKind::Internal, message)
+            }
+        }
+    }
+}
+
+/// Errors the app's own helpers report as plain text.
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        Self::new(ErrorKind::Internal, message)
+    }
+}
+
+impl From<tauri::Error> for CommandError {
+    fn from(error: tauri::Error) -> Self {
+        Self::new(ErrorKind::Internal, error.to_string())
+    }
+}
@@ -0,0 +1,150 @@
+//! Finished jobs, newest first, kept as `history.json` in the app data directory so the UI can
+//! list past jobs, re-run them or open their output.
+
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use zipx_core::errors::ExtractError;
+
+use crate::settings::save_json;
+
+/// Older records are dropped beyond this many.
+const MAX_RECORDS: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    Extract,
+    Compress,
+    Test,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcome {
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRecord {
+    pub job_id: String,
+    pub kind: JobKind,
+    pub input: String,
+    pub output: String,
+    pub format: String,
+    /// Seconds since the Unix epoch.
+    pub started_at: u64,
+    pub duration_ms: u64,
+    pub outcome: Outcome,
+    pub error: Option<String>,
+    /// The job's `ExtractReport` or `CompressReport`.
+    pub report: Option<serde_json::Value>,
+    pub warnings: Vec<String>,
+}
+
+/// A running job, turned into a [`JobRecord`] by [`Store::record`] once it ends.
+pub struct Started {
+    record: JobRecord,
+    clock: Instant,
+}
+
+impl Started {
+    pub fn new(kind: JobKind, job_id: &str, input: &str, output: &str, format: &str) -> Self {
+        let started_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        Started {
+            record: JobRecord {
+                job_id: job_id.to_string(),
+                kind,
+                input: input.to_string(),
+                output: output.to_string(),
+                format: format.to_string(),
+                started_at,
+                duration_ms: 0,
+                outcome: Outcome::Succeeded,
+                error: None,
+                report: None,
+                warnings: Vec::new(),
+            },
+            clock: Instant::now(),
+        }
+    }
+}
+
+pub struct Store {
+    /// `None` when the platform has no data directory; history then lasts for the session.
+    path: Option<PathBuf>,
+    records: Mutex<Vec<JobRecord>>,
+}
+
+impl Store {
+    /// Read the history at `path`; a missing or unreadable file starts it empty.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let records = path
+            .as_ref()
+            .and_then(|path| match std::fs::read(path) {
+                Ok(bytes) => serde_json::from_slice(&bytes)
+                    .map_err(|e| tracing::warn!("ignoring {}: {e}", path.display()))
+                    .ok(),
+                Err(_) => None,
+            })
+            .unwrap_or_default();
+        Store {
+            path,
+            records: Mutex::new(records),
+        }
+    }
+
+    pub fn list(&self) -> Vec<JobRecord> {
+        self.records.lock().map(|r| r.clone()).unwrap_or_default()
+    }
+
+    /// Add the outcome of `started`. Failing to save is logged, not reported: the job itself
+    /// has already finished.
+    pub fn record<T: Serialize>(&self, started: Started, result: &Result<T, ExtractError>) {
+        let mut record = started.record;
+        record.duration_ms = started.clock.elapsed().as_millis() as u64;
+        match result {
+            Ok(report) => {
+                let report = serde_json::to_value(report).ok();
+                record.warnings = report
+                    .as_ref()
+                    .and_then(|r| r.get("warnings"))
+                    .and_then(|w| serde_json::from_value(w.clone()).ok())
+                    .unwrap_or_default();
+                record.report = report;
+            }
+            Err(ExtractError::Cancelled) => record.outcome = Outcome::Cancelled,
+            Err(e) => {
+                record.outcome = Outcome::Failed;
+                record.error = Some(e.to_string());
+            }
+        }
+        let Ok(mut records) = self.records.lock() else {
+            return;
+        };
+        records.insert(0, record);
+        records.truncate(MAX_RECORDS);
+        if let Err(e) = self.save(&records) {
+            tracing::warn!("saving job history: {e}");
+        }
+    }
+
+    pub fn clear(&self) -> Result<(), String> {
+        let mut records = self.records.lock().map_err(|e| e.to_string())?;
+        records.clear();
+        self.save(&records)
+    }
+
+    fn save(&self, records: &[JobRecord]) -> Result<(), String> {
+        match &self.path {
+            Some(path) => save_json(path, &records),
+            None => Ok(()),
+        }
+    }
+}
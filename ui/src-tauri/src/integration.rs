@@ -0,0 +1,379 @@
+//! Registering the app with the desktop shell: "open with" associations for archive types and
+//! "Extract here" / "Extract to folder" context-menu entries. The entries launch the app with
+//! `--extract-here <archive>` or `--extract-to <archive>`, which [`launch_request`] turns into
+//! a request for the frontend.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+
+use serde::Serialize;
+use zipx_core::format_detection::archive_stem;
+
+/// Extensions handed to the shell. Windows only matches on the last extension, so `.tar.gz`
+/// is covered by `gz`.
+const EXTENSIONS: [&str; 13] = [
+    "zip", "tar", "gz", "tgz", "zst", "tzst", "lz4", "br", "xz", "bz2", "7z", "rar", "quench",
+];
+
+/// MIME types for the Linux `.desktop` entry.
+const MIME_TYPES: [&str; 10] = [
+    "application/zip",
+    "application/x-tar",
+    "application/gzip",
+    "application/x-compressed-tar",
+    "application/zstd",
+    "application/x-zstd-compressed-tar",
+    "application/x-lz4",
+    "application/x-xz",
+    "application/x-7z-compressed",
+    "application/vnd.rar",
+];
+
+const PROG_ID: &str = "Quench.Archive";
+const DESKTOP_FILE: &str = "quench.desktop";
+const EXTRACT_HERE: &str = "--extract-here";
+const EXTRACT_TO: &str = "--extract-to";
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LaunchAction {
+    /// Opened through a file association: show the archive, ready to extract.
+    Open,
+    /// Started from a context-menu entry: extract into `destination` straight away.
+    Extract,
+}
+
+/// What the app was started to do, taken once by the frontend.
+#[derive(Debug, Clone, Serialize)]
+pub struct LaunchRequest {
+    pub action: LaunchAction,
+    pub archive: PathBuf,
+    pub destination: Option<PathBuf>,
+}
+
+/// The launch request until the frontend asks for it.
+pub struct PendingLaunch(Mutex<Option<LaunchRequest>>);
+
+impl PendingLaunch {
+    pub fn new(request: Option<LaunchRequest>) -> Self {
+        PendingLaunch(Mutex::new(request))
+    }
+
+    pub fn take(&self) -> Option<LaunchRequest> {
+        self.0.lock().ok().and_then(|mut request| request.take())
+    }
+}
+
+/// The request in `args` (without the program name), if any.
+pub fn launch_request(args: impl IntoIterator<Item = String>) -> Option<LaunchRequest> {
+    let mut args = args.into_iter();
+    let first = args.next()?;
+    let (action, flag, archive) = match first.as_str() {
+        EXTRACT_HERE | EXTRACT_TO => (LaunchAction::Extract, Some(first.clone()), args.next()?),
+        _ if first.starts_with('-') => return None,
+        _ => (LaunchAction::Open, None, first),
+    };
+    // Shell scripts may pass paths relative to the folder being browsed.
+    let archive = std::fs::canonicalize(&archive).unwrap_or_else(|_| PathBuf::from(archive));
+    let parent = archive.parent().map(Path::to_path_buf).unwrap_or_default();
+    let destination = match flag.as_deref() {
+        Some(EXTRACT_HERE) => Some(parent),
+        Some(_) => Some(parent.join(archive_stem(&archive))),
+        None => None,
+    };
+    Some(LaunchRequest {
+        action,
+        archive,
+        destination,
+    })
+}
+
+/// Register the running executable as a handler for archive files and/or add the context-menu
+/// entries, for the current user only.
+pub fn register(associations: bool, context_menu: bool) -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    if cfg!(target_os = "windows") {
+        windows::register(&exe, associations, context_menu)
+    } else if cfg!(target_os = "macos") {
+        Err("on macOS file types are declared by the app bundle".to_string())
+    } else {
+        linux::register(&exe, associations, context_menu)
+    }
+}
+
+/// Remove everything [`register`] may have added. Entries that aren't there are skipped.
+pub fn unregister() -> Result<(), String> {
+    if cfg!(target_os = "windows") {
+        windows::unregister()
+    } else if cfg!(target_os = "macos") {
+        Ok(())
+    } else {
+        linux::unregister()
+    }
+}
+
+mod windows {
+    use super::*;
+
+    const CLASSES: &str = r"HKCU\Software\Classes";
+    const VERBS: [(&str, &str, &str); 2] = [
+        ("QuenchExtractHere", "Extract here", EXTRACT_HERE),
+        ("QuenchExtractTo", "Extract to folder", EXTRACT_TO),
+    ];
+
+    pub fn register(exe: &Path, associations: bool, context_menu: bool) -> Result<(), String> {
+        let exe = exe.display();
+        if associations {
+            let class = format!(r"{CLASSES}\{PROG_ID}");
+            reg_add(&class, None, "Quench archive")?;
+            reg_add(
+                &format!(r"{class}\DefaultIcon"),
+                None,
+                &format!("\"{exe}\",0"),
+            )?;
+            reg_add(
+                &format!(r"{class}\shell\open\command"),
+                None,
+                &format!("\"{exe}\" \"%1\""),
+            )?;
+            // Listed under "Open with" without taking over the user's chosen default.
+            for extension in EXTENSIONS {
+                reg_add(
+                    &format!(r"{CLASSES}\.{extension}\OpenWithProgids"),
+                    Some(PROG_ID),
+                    "",
+                )?;
+            }
+        }
+        if context_menu {
+            for extension in EXTENSIONS {
+                for (verb, label, flag) in VERBS {
+                    let key =
+                        format!(r"{CLASSES}\SystemFileAssociations\.{extension}\shell\{verb}");
+                    reg_add(&key, Some("MUIVerb"), label)?;
+                    reg_add(&key, Some("Icon"), &format!("\"{exe}\",0"))?;
+                    reg_add(
+                        &format!(r"{key}\command"),
+                        None,
+                        &format!("\"{exe}\" {flag} \"%1\""),
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn unregister() -> Result<(), String> {
+        reg_delete(&format!(r"{CLASSES}\{PROG_ID}"), None)?;
+        for extension in EXTENSIONS {
+            reg_delete(
+                &format!(r"{CLASSES}\.{extension}\OpenWithProgids"),
+                Some(PROG_ID),
+            )?;
+            for (verb, _, _) in VERBS {
+                reg_delete(
+                    &format!(r"{CLASSES}\SystemFileAssociations\.{extension}\shell\{verb}"),
+                    None,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn reg_add(key: &str, value: Option<&str>, data: &str) -> Result<(), String> {
+        let mut command = Command::new("reg");
+        command.args(["add", key]);
+        match value {
+            Some(name) => command.args(["/v", name]),
+            None => command.arg("/ve"),
+        };
+        command.args(["/t", "REG_SZ", "/d", data, "/f"]);
+        let output = command.output().map_err(|e| format!("reg: {e}"))?;
+        if !output.status.success() {
+            return Err(format!(
+                "reg add {key}: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Delete `key` or one value under it; a key or value that doesn't exist isn't an error.
+    fn reg_delete(key: &str, value: Option<&str>) -> Result<(), String> {
+        let exists = Command::new("reg")
+            .args(["query", key])
+            .output()
+            .map_err(|e| format!("reg: {e}"))?;
+        if !exists.status.success() {
+            return Ok(());
+        }
+        let mut command = Command::new("reg");
+        command.args(["delete", key]);
+        if let Some(name) = value {
+            command.args(["/v", name]);
+        }
+        command.arg("/f");
+        // Fails only when the value is missing, which is what we want anyway.
+        let _ = command.output();
+        Ok(())
+    }
+}
+
+mod linux {
+    use super::*;
+
+    const SCRIPTS: [(&str, &str); 2] = [
+        ("Quench - Extract here", EXTRACT_HERE),
+        ("Quench - Extract to folder", EXTRACT_TO),
+    ];
+
+    fn data_dir() -> Result<PathBuf, String> {
+        tauri::api::path::data_dir().ok_or_else(|| "no data directory for this user".to_string())
+    }
+
+    fn desktop_path() -> Result<PathBuf, String> {
+        Ok(data_dir()?.join("applications").join(DESKTOP_FILE))
+    }
+
+    fn script_path(name: &str) -> Result<PathBuf, String> {
+        Ok(data_dir()?.join("nautilus").join("scripts").join(name))
+    }
+
+    /// `arg` as one quoted argument of a desktop entry's `Exec` key: `"`, `` ` ``, `$` and
+    /// `\` are escaped for the quoting, and those escapes once more for the string value the
+    /// key holds; `%` would start a field code.
+    fn exec_arg(arg: &str) -> String {
+        let mut quoted = String::from("\"");
+        for c in arg.chars() {
+            match c {
+                '"' | '`' | '$' => quoted.push_str(&format!("\\\\{c}")),
+                '\\' => quoted.push_str("\\\\\\\\"),
+                '%' => quoted.push_str("%%"),
+                '\n' => quoted.push_str("\\n"),
+                c => quoted.push(c),
+            }
+        }
+        quoted.push('"');
+        quoted
+    }
+
+    /// `arg` single-quoted for `/bin/sh`.
+    fn shell_arg(arg: &str) -> String {
+        format!("'{}'", arg.replace('\'', r"'\''"))
+    }
+
+    pub fn register(exe: &Path, associations: bool, context_menu: bool) -> Result<(), String> {
+        let exe = exe.to_string_lossy();
+        let exec = exec_arg(&exe);
+        // The entry doubles as the launcher the context-menu actions hang off, so it is
+        // written either way; only `MimeType` makes it an "open with" candidate.
+        let mut entry = format!(
+            "[Desktop Entry]\nType=Application\nName=Quench\nExec={exec} %f\nTerminal=false\nCategories=Utility;Archiving;\n"
+        );
+        if associations {
+            entry.push_str(&format!("MimeType={};\n", MIME_TYPES.join(";")));
+        }
+        if context_menu {
+            entry.push_str("Actions=ExtractHere;ExtractTo;\n");
+            entry.push_str(&format!("\n[Desktop Action ExtractHere]\nName=Extract here\nExec={exec} {EXTRACT_HERE} %f\n"));
+            entry.push_str(&format!("\n[Desktop Action ExtractTo]\nName=Extract to folder\nExec={exec} {EXTRACT_TO} %f\n"));
+        }
+        write(&desktop_path()?, &entry, false)?;
+
+        if associations {
+            for mime in MIME_TYPES {
+                // Best effort: without xdg-utils the MimeType line still lists the app.
+                let _ = Command::new("xdg-mime")
+                    .args(["default", DESKTOP_FILE, mime])
+                    .output();
+            }
+        }
+        if context_menu {
+            let exe = shell_arg(&exe);
+            for (name, flag) in SCRIPTS {
+                let script = format!(
+                    "#!/bin/sh\nfor archive in \"$@\"; do\n  {exe} {flag} \"$archive\" &\ndone\n"
+                );
+                write(&script_path(name)?, &script, true)?;
+            }
+        }
+        refresh_database();
+        Ok(())
+    }
+
+    pub fn unregister() -> Result<(), String> {
+        remove(&desktop_path()?)?;
+        for (name, _) in SCRIPTS {
+            remove(&script_path(name)?)?;
+        }
+        if let Some(config) = tauri::api::path::config_dir() {
+            forget_defaults(&config.join("mimeapps.list"))?;
+        }
+        refresh_database();
+        Ok(())
+    }
+
+    fn write(path: &Path, contents: &str, executable: bool) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        std::fs::write(path, contents).map_err(|e| format!("{}: {e}", path.display()))?;
+        #[cfg(unix)]
+        if executable {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755))
+                .map_err(|e| e.to_string())?;
+        }
+        #[cfg(not(unix))]
+        let _ = executable;
+        Ok(())
+    }
+
+    fn remove(path: &Path) -> Result<(), String> {
+        match std::fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => {
+                Err(format!("{}: {e}", path.display()))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Drop our desktop file from the defaults `xdg-mime` recorded, leaving other apps' alone.
+    fn forget_defaults(mimeapps: &Path) -> Result<(), String> {
+        let Ok(contents) = std::fs::read_to_string(mimeapps) else {
+            return Ok(());
+        };
+        let mut cleaned = String::with_capacity(contents.len());
+        for line in contents.lines() {
+            match line.split_once('=') {
+                Some((mime, apps)) if !line.starts_with('[') && apps.contains(DESKTOP_FILE) => {
+                    let apps: Vec<&str> = apps
+                        .split(';')
+                        .filter(|a| !a.is_empty() && *a != DESKTOP_FILE)
+                        .collect();
+                    if !apps.is_empty() {
+                        cleaned.push_str(&format!("{mime}={};\n", apps.join(";")));
+                    }
+                }
+                _ => {
+                    cleaned.push_str(line);
+                    cleaned.push('\n');
+                }
+            }
+        }
+        if cleaned != contents {
+            std::fs::write(mimeapps, cleaned)
+                .map_err(|e| format!("{}: {e}", mimeapps.display()))?;
+        }
+        Ok(())
+    }
+
+    fn refresh_database() {
+        if let Ok(dir) = data_dir() {
+            let _ = Command::new("update-desktop-database")
+                .arg(dir.join("applications"))
+                .output();
+        }
+    }
+}
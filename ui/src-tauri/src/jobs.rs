@@ -0,0 +1,363 @@
+//! Job ids, cancellation of running jobs and forwarding of core progress channels to window
+//! events.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::api::notification::Notification;
+use tauri::{Manager, Window};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedSender};
+use tokio_util::sync::CancellationToken;
+use zipx_core::containers::{PauseToken, ProgressInfo};
+use zipx_core::errors::ExtractError;
+
+use crate::errors::CommandError;
+use crate::history::JobKind;
+
+pub const EXTRACT_PROGRESS: &str = "extract://progress";
+pub const COMPRESS_PROGRESS: &str = "compress://progress";
+pub const TEST_PROGRESS: &str = "test://progress";
+pub const BATCH_ITEM: &str = "batch://item";
+pub const BATCH_PROGRESS: &str = "batch://progress";
+pub const JOB_CANCELLED: &str = "job://cancelled";
+pub const JOB_PASSWORD_REQUIRED: &str = "job://password-required";
+pub const JOB_STATUS: &str = "job://status";
+
+/// Minimum gap between progress events so archives of tiny files don't flood the webview.
+const EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// An id for a job the frontend didn't name itself.
+pub fn new_job_id() -> String {
+    static NEXT: AtomicU64 = AtomicU64::new(1);
+    format!("job-{}", NEXT.fetch_add(1, Ordering::Relaxed))
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemStatus {
+    Queued,
+    Done,
+    Failed,
+}
+
+/// Status of one archive of a batch job; progress for it arrives as `extract://progress`
+/// events with the same `job_id` and `archive` set to `index`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchItemEvent {
+    pub job_id: String,
+    pub index: usize,
+    pub path: String,
+    pub status: ItemStatus,
+    pub outcome: Option<zipx_core::pipeline::ArchiveOutcome>,
+}
+
+/// Totals across every archive of a batch job.
+#[derive(Debug, Clone, Serialize)]
+pub struct BatchProgressEvent {
+    pub job_id: String,
+    pub total_archives: usize,
+    pub archives_done: usize,
+    pub files_processed: u64,
+    pub bytes_processed: u64,
+    pub elapsed_ms: u64,
+    /// Bytes per second since the job started.
+    pub throughput: f64,
+    /// Bytes per second over the last few seconds, across the archives still running.
+    pub rate: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobEvent {
+    pub job_id: String,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    Paused,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatusEvent {
+    pub job_id: String,
+    pub state: JobState,
+}
+
+/// Handles for steering one running job, passed on to the core options.
+#[derive(Debug, Clone, Default)]
+pub struct Control {
+    pub cancel: CancellationToken,
+    pub pause: PauseToken,
+}
+
+struct Running {
+    control: Control,
+    kind: JobKind,
+    /// What the job works on, for its completion notification.
+    name: String,
+}
+
+/// The jobs currently running, by job id.
+#[derive(Default)]
+pub struct Jobs {
+    running: Mutex<HashMap<String, Running>>,
+    /// Set once the window was closed with jobs still running; the app then quits when the
+    /// last of them finishes.
+    detached: AtomicBool,
+}
+
+impl Jobs {
+    pub fn start(&self, job_id: &str, kind: JobKind, name: &str) -> Control {
+        let control = Control::default();
+        if let Ok(mut running) = self.running.lock() {
+            let job = Running {
+                control: control.clone(),
+                kind,
+                name: name.to_string(),
+            };
+            running.insert(job_id.to_string(), job);
+        }
+        control
+    }
+
+    fn get(&self, job_id: &str) -> Option<Control> {
+        self.running
+            .lock()
+            .ok()
+            .and_then(|running| running.get(job_id).map(|job| job.control.clone()))
+    }
+
+    pub fn any_running(&self) -> bool {
+        self.running.lock().is_ok_and(|running| !running.is_empty())
+    }
+
+    /// Keep the jobs going after the window closes; see [`Jobs::finish`].
+    pub fn detach(&self) {
+        self.detached.store(true, Ordering::Relaxed);
+    }
+
+    /// Returns false if no job with that id is running.
+    pub fn cancel(&self, job_id: &str) -> bool {
+        match self.get(job_id) {
+            Some(control) => {
+                control.cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Pause or resume `job_id` and announce it with a [`JOB_STATUS`] event. Returns false if
+    /// no job with that id is running.
+    pub fn set_paused(&self, window: &Window, job_id: &str, paused: bool) -> bool {
+        let Some(control) = self.get(job_id) else {
+            return false;
+        };
+        let state = if paused {
+            control.pause.pause();
+            JobState::Paused
+        } else {
+            control.pause.resume();
+            JobState::Running
+        };
+        let _ = window.emit(
+            JOB_STATUS,
+            JobStatusEvent {
+                job_id: job_id.to_string(),
+                state,
+            },
+        );
+        true
+    }
+
+    /// Unregister `job_id` and turn its result into a command result, emitting
+    /// [`JOB_CANCELLED`] or [`JOB_PASSWORD_REQUIRED`] when the job stopped for either reason.
+    /// A job that ends while the window is hidden or in the background gets a native
+    /// notification; once the window has been closed, the last job to end quits the app.
+    pub fn finish<T>(
+        &self,
+        window: &Window,
+        job_id: &str,
+        result: zipx_core::errors::Result<T>,
+    ) -> Result<T, CommandError> {
+        let (job, idle) = match self.running.lock() {
+            Ok(mut running) => (running.remove(job_id), running.is_empty()),
+            Err(_) => (None, false),
+        };
+        let detached = self.detached.load(Ordering::Relaxed);
+        let unattended = detached
+            || !window.is_visible().unwrap_or(false)
+            || !window.is_focused().unwrap_or(false);
+        if let (Some(job), true) = (job, unattended) {
+            notify(window, &job, &result);
+        }
+        if detached && idle {
+            window.app_handle().exit(0);
+        }
+        let event = match &result {
+            Err(ExtractError::Cancelled) => Some(JOB_CANCELLED),
+            Err(ExtractError::Password) => Some(JOB_PASSWORD_REQUIRED),
+            _ => None,
+        };
+        if let Some(event) = event {
+            let _ = window.emit(
+                event,
+                JobEvent {
+                    job_id: job_id.to_string(),
+                },
+            );
+        }
+        result.map_err(CommandError::from)
+    }
+}
+
+fn notify<T>(window: &Window, job: &Running, result: &zipx_core::errors::Result<T>) {
+    let task = match job.kind {
+        JobKind::Extract => "Extraction",
+        JobKind::Compress => "Compression",
+        JobKind::Test => "Archive test",
+    };
+    let (title, body) = match result {
+        Ok(_) => (format!("{task} finished"), job.name.clone()),
+        Err(ExtractError::Cancelled) => return,
+        Err(e) => (format!("{task} failed"), format!("{}: {e}", job.name)),
+    };
+    let identifier = window.app_handle().config().tauri.bundle.identifier.clone();
+    if let Err(e) = Notification::new(identifier).title(title).body(body).show() {
+        tracing::warn!("showing notification: {e}");
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProgressEvent {
+    pub job_id: String,
+    #[serde(flatten)]
+    pub info: ProgressInfo,
+    pub elapsed_ms: u64,
+    /// Bytes per second since the job started.
+    pub throughput: f64,
+}
+
+/// A sender for core progress updates; they are re-emitted to `window` as `event`, throttled,
+/// with the last update always delivered once the job drops its sender.
+pub fn forward_progress(
+    window: Window,
+    event: &'static str,
+    job_id: String,
+) -> UnboundedSender<ProgressInfo> {
+    let (sender, mut receiver) = unbounded_channel::<ProgressInfo>();
+    tauri::async_runtime::spawn(async move {
+        let started = Instant::now();
+        let mut last_emit: Option<Instant> = None;
+        let mut pending = None;
+        let emit = |info: ProgressInfo| {
+            let elapsed = started.elapsed();
+            let throughput = match elapsed.as_secs_f64() {
+                secs if secs > 0.0 => info.bytes_processed as f64 / secs,
+                _ => 0.0,
+            };
+            let payload = ProgressEvent {
+                job_id: job_id.clone(),
+                info,
+                elapsed_ms: elapsed.as_millis() as u64,
+                throughput,
+            };
+            let _ = window.emit(event, payload);
+        };
+        while let Some(info) = receiver.recv().await {
+            if last_emit.is_some_and(|at| at.elapsed() < EMIT_INTERVAL) {
+                pending = Some(info);
+                continue;
+            }
+            pending = None;
+            last_emit = Some(Instant::now());
+            emit(info);
+        }
+        if let Some(info) = pending {
+            emit(info);
+        }
+    });
+    sender
+}
+
+/// Like [`forward_progress`] for a batch of `total_archives`: each archive's updates go out as
+/// `extract://progress` events (throttled per archive) along with a `batch://progress` total.
+pub fn forward_batch_progress(
+    window: Window,
+    job_id: String,
+    total_archives: usize,
+) -> UnboundedSender<ProgressInfo> {
+    let (sender, mut receiver) = unbounded_channel::<ProgressInfo>();
+    tauri::async_runtime::spawn(async move {
+        let started = Instant::now();
+        let mut latest: Vec<ProgressInfo> = vec![ProgressInfo::default(); total_archives];
+        let mut done = vec![false; total_archives];
+        let mut dirty = vec![false; total_archives];
+        let mut last_emit: Option<Instant> = None;
+
+        let emit = |latest: &[ProgressInfo], done: &[bool], dirty: &mut [bool]| {
+            let elapsed = started.elapsed();
+            let per_second = |bytes: u64| match elapsed.as_secs_f64() {
+                secs if secs > 0.0 => bytes as f64 / secs,
+                _ => 0.0,
+            };
+            for (info, dirty) in latest.iter().zip(dirty.iter_mut()).filter(|(_, d)| **d) {
+                *dirty = false;
+                let payload = ProgressEvent {
+                    job_id: job_id.clone(),
+                    info: info.clone(),
+                    elapsed_ms: elapsed.as_millis() as u64,
+                    throughput: per_second(info.bytes_processed),
+                };
+                let _ = window.emit(EXTRACT_PROGRESS, payload);
+            }
+            let bytes_processed = latest.iter().map(|i| i.bytes_processed).sum();
+            let _ = window.emit(
+                BATCH_PROGRESS,
+                BatchProgressEvent {
+                    job_id: job_id.clone(),
+                    total_archives,
+                    archives_done: done.iter().filter(|&&d| d).count(),
+                    files_processed: latest.iter().map(|i| i.files_processed).sum(),
+                    bytes_processed,
+                    elapsed_ms: elapsed.as_millis() as u64,
+                    throughput: per_second(bytes_processed),
+                    rate: latest
+                        .iter()
+                        .zip(done)
+                        .filter(|(_, &d)| !d)
+                        .map(|(i, _)| i.rate)
+                        .sum(),
+                },
+            );
+        };
+
+        while let Some(info) = receiver.recv().await {
+            let Some(index) = info.archive.filter(|&i| i < total_archives) else {
+                continue;
+            };
+            // A finished archive is always reported straight away so its bar completes.
+            let finished = info.archive_done;
+            if finished {
+                done[index] = true;
+                latest[index].archive_done = true;
+            } else {
+                latest[index] = info;
+            }
+            dirty[index] = true;
+            if !finished && last_emit.is_some_and(|at| at.elapsed() < EMIT_INTERVAL) {
+                continue;
+            }
+            last_emit = Some(Instant::now());
+            emit(&latest, &done, &mut dirty);
+        }
+        if dirty.iter().any(|&d| d) {
+            emit(&latest, &done, &mut dirty);
+        }
+    });
+    sender
+}
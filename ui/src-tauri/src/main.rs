@@ -1,12 +1,29 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod browse;
+mod desktop;
+mod errors;
+mod history;
+mod integration;
+mod jobs;
+mod password;
+mod settings;
+mod thumbnails;
+
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
 use serde::{Deserialize, Serialize};
+use errors::CommandError;
+use tauri::Manager;
+use zipx_core::checksums::{self, HashAlgorithm};
 use zipx_core::containers::{ExtractOptions, ExtractReport};
+use zipx_core::errors::ExtractError;
 use zipx_core::format_detection;
-use zipx_core::pipeline::{CompressOptions, CompressReport, Extractor};
+use zipx_core::pipeline::{
+    BatchExtractReport, CompressOptions, CompressReport, CompressionProfile, Extractor,
+};
+use zipx_core::reader::{ArchiveReader, VerifyReport};
 use zipx_core::resilience::IntegrityPolicy;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -59,109 +76,614 @@ fn run_7za_extract(archive: &Path, destination: &Path) -> Result<ExternalExtract
     })
 }
 
-fn run_7za_compress(source: &Path, destination: &Path) -> Result<CompressReport, String> {
-    let exe = resource_7za_path()?;
-    if !exe.exists() {
-        return Err("7za.exe is missing from app resources".to_string());
-    }
-    let output = Command::new(exe)
-        .arg("a")
-        .arg("-t7z")
-        .arg(destination)
-        .arg(source)
-        .output()
-        .map_err(|e| e.to_string())?;
-
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr).to_string();
-        return Err(format!("7z compress failed: {}", stderr.trim()));
-    }
-
-    let bytes_written = std::fs::metadata(destination)
-        .map(|m| m.len())
-        .unwrap_or(0);
-
-    Ok(CompressReport {
-        files: 0,
-        bytes_read: 0,
-        bytes_written,
-        compression_ratio: 0.0,
-    })
-}
-
 #[tauri::command]
-async fn detect_format(path: String) -> Result<String, String> {
+async fn detect_format(path: String) -> Result<String, CommandError> {
     let path_obj = std::path::PathBuf::from(path);
     format_detection::detect_format(&path_obj)
         .map(|fmt| fmt.as_str().to_string())
-        .map_err(|e| e.to_string())
+        .map_err(CommandError::from)
+}
+
+/// `format`, or the format detected from `path`'s contents when it is "auto".
+fn resolve_format(path: &str, format: String) -> String {
+    if format != "auto" {
+        return format;
+    }
+    match format_detection::detect_format(Path::new(path)) {
+        Ok(fmt) => fmt.as_str().to_string(),
+        Err(_) => format,
+    }
 }
 
+/// The file name of `path`, for notifications.
+fn display_name(path: &str) -> String {
+    Path::new(path).file_name().map_or_else(
+        || path.to_string(),
+        |name| name.to_string_lossy().into_owned(),
+    )
+}
+
+/// Progress is emitted as `extract://progress` events tagged with `job_id`; the job can be
+/// stopped with `cancel_job`. Fails with kind `password_required` when `password` is missing or
+/// wrong for an encrypted archive.
 #[tauri::command]
 async fn extract_archive(
+    window: tauri::Window,
+    jobs: tauri::State<'_, jobs::Jobs>,
     path: String,
     destination: String,
     format: String,
-) -> Result<ExtractReport, String> {
-    // Auto-detect format if "auto" is specified
-    let detected_format = if format == "auto" {
-        let path_obj = std::path::PathBuf::from(&path);
-        match format_detection::detect_format(&path_obj) {
-            Ok(fmt) => fmt.as_str().to_string(),
-            Err(_) => format,
-        }
-    } else {
-        format
-    };
+    password: Option<String>,
+    job_id: Option<String>,
+) -> Result<ExtractReport, CommandError> {
+    let job_id = job_id.unwrap_or_else(jobs::new_job_id);
+    let detected_format = resolve_format(&path, format);
+    let extractor = Extractor::with_defaults();
 
-    if detected_format == "7z" || detected_format == "rar" {
+    // The bundled 7za (Windows only) covers rar until core has a container for it.
+    let native = extractor.formats().contains(&detected_format.as_str());
+    if !native && detected_format == "rar" {
         let report = run_7za_extract(Path::new(&path), Path::new(&destination))?;
         return Ok(ExtractReport {
             entries: report.entries,
             bytes_written: report.bytes_written,
             warnings: report.warnings,
+            ..Default::default()
         });
     }
 
-    let file = tokio::fs::File::open(&path)
-        .await
-        .map_err(|e| e.to_string())?;
+    let started = history::Started::new(
+        history::JobKind::Extract,
+        &job_id,
+        &path,
+        &destination,
+        &detected_format,
+    );
+    let file = tokio::fs::File::open(&path).await?;
     let reader = tokio::io::BufReader::new(file);
-    let mut options = ExtractOptions::default();
-    options.destination = std::path::PathBuf::from(destination);
-    options.integrity = IntegrityPolicy::default();
+    let control = jobs.start(&job_id, history::JobKind::Extract, &display_name(&path));
+    let mut options = ExtractOptions {
+        destination: std::path::PathBuf::from(destination),
+        integrity: IntegrityPolicy::default(),
+        password,
+        single_file_name: Some(format_detection::archive_stem(Path::new(&path))),
+        progress: Some(jobs::forward_progress(
+            window.clone(),
+            jobs::EXTRACT_PROGRESS,
+            job_id.clone(),
+        )),
+        cancel: Some(control.cancel),
+        pause: Some(control.pause),
+        ..Default::default()
+    };
+    window
+        .state::<settings::Store>()
+        .get()
+        .apply_extract(&mut options);
+    let result = extractor.extract(&detected_format, reader, options).await;
+    window.state::<history::Store>().record(started, &result);
+    jobs.finish(&window, &job_id, result)
+}
+
+/// Extract only `entries` from the archive at `path`. Each is an entry path as returned by
+/// `list_archive` or a glob; a directory selects everything below it. Progress and cancellation
+/// work as for `extract_archive`.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+async fn extract_entries(
+    window: tauri::Window,
+    jobs: tauri::State<'_, jobs::Jobs>,
+    path: String,
+    destination: String,
+    format: String,
+    entries: Vec<String>,
+    password: Option<String>,
+    job_id: Option<String>,
+) -> Result<ExtractReport, CommandError> {
+    if entries.is_empty() {
+        return Err(CommandError::invalid("No entries selected"));
+    }
+    let job_id = job_id.unwrap_or_else(jobs::new_job_id);
+    let detected_format = resolve_format(&path, format);
     let extractor = Extractor::with_defaults();
-    extractor
-        .extract(&detected_format, reader, options)
-        .await
-        .map(|report| report)
-        .map_err(|e| e.to_string())
+    if !extractor.formats().contains(&detected_format.as_str()) {
+        return Err(CommandError::unsupported(format!(
+            "Extracting selected entries is not supported for {detected_format}"
+        )));
+    }
+
+    let started = history::Started::new(
+        history::JobKind::Extract,
+        &job_id,
+        &path,
+        &destination,
+        &detected_format,
+    );
+    let file = tokio::fs::File::open(&path).await?;
+    let reader = tokio::io::BufReader::new(file);
+    let control = jobs.start(&job_id, history::JobKind::Extract, &display_name(&path));
+    let mut options = ExtractOptions {
+        destination: std::path::PathBuf::from(destination),
+        include: Some(entries),
+        password,
+        progress: Some(jobs::forward_progress(
+            window.clone(),
+            jobs::EXTRACT_PROGRESS,
+            job_id.clone(),
+        )),
+        cancel: Some(control.cancel),
+        pause: Some(control.pause),
+        ..Default::default()
+    };
+    window
+        .state::<settings::Store>()
+        .get()
+        .apply_extract(&mut options);
+    let result = extractor.extract(&detected_format, reader, options).await;
+    window.state::<history::Store>().record(started, &result);
+    jobs.finish(&window, &job_id, result)
 }
 
+/// Extract several archives, each into a folder named after it under `destination`. Every
+/// archive is announced as a `batch://item` event, then reported done or failed the same way.
+/// Progress arrives per archive as `extract://progress` events (with `archive` set to its
+/// index) and for the whole batch as `batch://progress` events.
 #[tauri::command]
+async fn batch_extract(
+    window: tauri::Window,
+    jobs: tauri::State<'_, jobs::Jobs>,
+    paths: Vec<String>,
+    destination: String,
+    password: Option<String>,
+    job_id: Option<String>,
+) -> Result<BatchExtractReport, CommandError> {
+    let job_id = job_id.unwrap_or_else(jobs::new_job_id);
+    let item = |index: usize, path: &str, status, outcome| jobs::BatchItemEvent {
+        job_id: job_id.clone(),
+        index,
+        path: path.to_string(),
+        status,
+        outcome,
+    };
+    let mut archives = Vec::with_capacity(paths.len());
+    for (index, path) in paths.iter().enumerate() {
+        let _ = window.emit(
+            jobs::BATCH_ITEM,
+            item(index, path, jobs::ItemStatus::Queued, None),
+        );
+        let input = PathBuf::from(path);
+        let output = Path::new(&destination).join(format_detection::archive_stem(&input));
+        archives.push((input, output));
+    }
+
+    let control = jobs.start(
+        &job_id,
+        history::JobKind::Extract,
+        &format!("{} archives", paths.len()),
+    );
+    let mut options = ExtractOptions {
+        password,
+        progress: Some(jobs::forward_batch_progress(
+            window.clone(),
+            job_id.clone(),
+            paths.len(),
+        )),
+        cancel: Some(control.cancel),
+        pause: Some(control.pause),
+        ..Default::default()
+    };
+    window
+        .state::<settings::Store>()
+        .get()
+        .apply_extract(&mut options);
+    let extractor = Extractor::with_defaults();
+    let result = extractor.batch_extract(archives, options).await;
+    if let Ok(report) = &result {
+        for (index, (path, outcome)) in paths.iter().zip(&report.archives).enumerate() {
+            let status = if outcome.error.is_some() {
+                jobs::ItemStatus::Failed
+            } else {
+                jobs::ItemStatus::Done
+            };
+            let _ = window.emit(
+                jobs::BATCH_ITEM,
+                item(index, path, status, Some(outcome.clone())),
+            );
+        }
+    }
+    jobs.finish(&window, &job_id, result)
+}
+
+/// Read every file of the archive at `path` without writing anything, checking it decodes and
+/// (for zip) matches its CRC. Progress arrives as `test://progress` events; the job can be
+/// stopped with `cancel_job`. Damaged entries are listed in the report rather than failing it.
+#[tauri::command]
+async fn test_archive(
+    window: tauri::Window,
+    jobs: tauri::State<'_, jobs::Jobs>,
+    path: String,
+    format: Option<String>,
+    password: Option<String>,
+    job_id: Option<String>,
+) -> Result<VerifyReport, CommandError> {
+    let job_id = job_id.unwrap_or_else(jobs::new_job_id);
+    let format = resolve_format(&path, format.unwrap_or_else(|| "auto".to_string()));
+    let started = history::Started::new(history::JobKind::Test, &job_id, &path, "", &format);
+    let control = jobs.start(&job_id, history::JobKind::Test, &display_name(&path));
+    let progress = jobs::forward_progress(window.clone(), jobs::TEST_PROGRESS, job_id.clone());
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        ArchiveReader::open(Path::new(&path), &format)?
+            .with_password(password)
+            .verify(Some(&progress), Some(&control.cancel))
+    })
+    .await?;
+    window.state::<history::Store>().record(started, &result);
+    jobs.finish(&window, &job_id, result)
+}
+
+/// Compression settings beyond format and level; anything left out keeps its default.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct CompressExtras {
+    include: Option<Vec<String>>,
+    exclude: Option<Vec<String>>,
+    deterministic: bool,
+    threads: Option<u32>,
+    profile: Option<CompressionProfile>,
+    /// Further files or folders added beside `source`; then each goes under its own name.
+    additional_sources: Vec<String>,
+    /// Encrypt the archive (AES zip, 7z with encrypted names, or quench).
+    password: Option<String>,
+    /// Must match `password` when given.
+    password_confirmation: Option<String>,
+}
+
+/// Progress is emitted as `compress://progress` events tagged with `job_id`; the job can be
+/// stopped with `cancel_job`, which also removes the partial archive.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
 async fn compress_archive(
+    window: tauri::Window,
+    jobs: tauri::State<'_, jobs::Jobs>,
     source: String,
     destination: String,
     format: String,
     level: Option<u32>,
-) -> Result<CompressReport, String> {
-    if format == "7z" {
-        return run_7za_compress(Path::new(&source), Path::new(&destination));
+    options: Option<CompressExtras>,
+    job_id: Option<String>,
+) -> Result<CompressReport, CommandError> {
+    let extras = options.unwrap_or_default();
+    if let Some(password) = &extras.password {
+        password::validate(password, extras.password_confirmation.as_deref())
+            .map_err(CommandError::invalid)?;
+        if !matches!(format.as_str(), "7z" | "zip" | "quench") {
+            return Err(CommandError::invalid(format!(
+                "{format} archives cannot be encrypted; choose zip, 7z or quench"
+            )));
+        }
     }
+    let job_id = job_id.unwrap_or_else(jobs::new_job_id);
     if format == "rar" {
-        return Err("RAR compression is not supported".to_string());
+        return Err(CommandError::unsupported(
+            "RAR compression is not supported",
+        ));
     }
-    let mut options = CompressOptions::default();
-    options.source = std::path::PathBuf::from(source);
-    options.destination = std::path::PathBuf::from(destination);
-    options.format = format;
-    options.compression_level = level;
+    let started = history::Started::new(
+        history::JobKind::Compress,
+        &job_id,
+        &source,
+        &destination,
+        &format,
+    );
+    let control = jobs.start(
+        &job_id,
+        history::JobKind::Compress,
+        &display_name(&destination),
+    );
+    let mut options = CompressOptions {
+        sources: std::iter::once(source)
+            .chain(extras.additional_sources)
+            .map(PathBuf::from)
+            .collect(),
+        destination: std::path::PathBuf::from(destination),
+        format,
+        compression_level: level,
+        include: extras.include,
+        exclude: extras.exclude,
+        deterministic: extras.deterministic,
+        threads: extras.threads,
+        profile: extras.profile,
+        password: extras.password,
+        progress: Some(jobs::forward_progress(
+            window.clone(),
+            jobs::COMPRESS_PROGRESS,
+            job_id.clone(),
+        )),
+        cancel: Some(control.cancel),
+        pause: Some(control.pause),
+        ..Default::default()
+    };
+    window
+        .state::<settings::Store>()
+        .get()
+        .apply_compress(&mut options);
     let extractor = Extractor::with_defaults();
-    extractor
-        .compress(options)
-        .await
-        .map(|report| report)
-        .map_err(|e| e.to_string())
+    let result = extractor.compress(options).await;
+    window.state::<history::Store>().record(started, &result);
+    jobs.finish(&window, &job_id, result)
+}
+
+/// Live feedback for the password field of the create-archive dialog.
+#[tauri::command]
+fn check_password_strength(password: String) -> password::Strength {
+    password::strength(&password)
+}
+
+/// Stop a running extract or compress job. Files it already wrote are removed and the job's
+/// command fails with kind `cancelled` after a `job://cancelled` event.
+#[tauri::command]
+fn cancel_job(jobs: tauri::State<'_, jobs::Jobs>, job_id: String) -> Result<(), CommandError> {
+    if jobs.cancel(&job_id) {
+        Ok(())
+    } else {
+        Err(CommandError::not_found(format!("no running job {job_id}")))
+    }
+}
+
+/// Hold a running job between reads, e.g. to free up disk bandwidth for a while; a
+/// `job://status` event reports the new state.
+#[tauri::command]
+fn pause_job(
+    window: tauri::Window,
+    jobs: tauri::State<'_, jobs::Jobs>,
+    job_id: String,
+) -> Result<(), CommandError> {
+    if jobs.set_paused(&window, &job_id, true) {
+        Ok(())
+    } else {
+        Err(CommandError::not_found(format!("no running job {job_id}")))
+    }
+}
+
+/// Continue a job held by [`pause_job`].
+#[tauri::command]
+fn resume_job(
+    window: tauri::Window,
+    jobs: tauri::State<'_, jobs::Jobs>,
+    job_id: String,
+) -> Result<(), CommandError> {
+    if jobs.set_paused(&window, &job_id, false) {
+        Ok(())
+    } else {
+        Err(CommandError::not_found(format!("no running job {job_id}")))
+    }
+}
+
+/// The entries of the archive at `path` as a tree, without extracting anything.
+#[tauri::command]
+async fn list_archive(
+    path: String,
+    format: Option<String>,
+    password: Option<String>,
+) -> Result<Vec<browse::ArchiveNode>, CommandError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let format = format.unwrap_or_else(|| "auto".to_string());
+        let reader = ArchiveReader::open(Path::new(&path), &format)?.with_password(password);
+        Ok::<_, ExtractError>(browse::tree(reader.entries()?))
+    })
+    .await?
+    .map_err(CommandError::from)
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct FileHash {
+    algorithm: HashAlgorithm,
+    digest: String,
+    /// Whether `digest` equals the `expected` value passed in, if one was.
+    matches: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct EntryHash {
+    path: String,
+    size: u64,
+    digest: String,
+}
+
+/// Checksum of a file on disk (SHA-256 unless `algo` says "blake3"), optionally compared with
+/// an expected hex digest.
+#[tauri::command]
+async fn hash_file(
+    path: String,
+    algo: Option<HashAlgorithm>,
+    expected: Option<String>,
+) -> Result<FileHash, CommandError> {
+    let algorithm = algo.unwrap_or_default();
+    let digest = tauri::async_runtime::spawn_blocking(move || {
+        checksums::hash_file(Path::new(&path), algorithm)
+    })
+    .await??;
+    let matches = expected.map(|e| e.trim().eq_ignore_ascii_case(&digest));
+    Ok(FileHash {
+        algorithm,
+        digest,
+        matches,
+    })
+}
+
+/// Checksums of every file inside the archive at `path`, in archive order.
+#[tauri::command]
+async fn hash_archive_entries(
+    path: String,
+    algo: Option<HashAlgorithm>,
+    format: Option<String>,
+    password: Option<String>,
+) -> Result<Vec<EntryHash>, CommandError> {
+    let algorithm = algo.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        let format = format.unwrap_or_else(|| "auto".to_string());
+        let reader = ArchiveReader::open(Path::new(&path), &format)?.with_password(password);
+        let mut hashes = Vec::new();
+        reader.for_each_file(|entry, contents| {
+            hashes.push(EntryHash {
+                path: entry.path.to_string_lossy().into_owned(),
+                size: entry.size,
+                digest: checksums::hash_reader(contents, algorithm)?,
+            });
+            Ok(())
+        })?;
+        Ok::<_, ExtractError>(hashes)
+    })
+    .await?
+    .map_err(CommandError::from)
+}
+
+/// A JPEG thumbnail of the image entry `entry`, at most `size` pixels (256 by default) on
+/// its longer side. Results are cached per archive and entry.
+#[tauri::command]
+async fn get_thumbnail(
+    window: tauri::Window,
+    path: String,
+    entry: String,
+    size: Option<u32>,
+    format: Option<String>,
+    password: Option<String>,
+) -> Result<thumbnails::Thumbnail, CommandError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let format = format.unwrap_or_else(|| "auto".to_string());
+        let reader = ArchiveReader::open(Path::new(&path), &format)?.with_password(password);
+        let size = size.unwrap_or(thumbnails::DEFAULT_SIZE).clamp(16, 1024);
+        window
+            .state::<thumbnails::Cache>()
+            .get(&reader, Path::new(&path), &entry, size)
+    })
+    .await?
+    .map_err(CommandError::from)
+}
+
+/// Entry count and uncompressed size of the archive at `path`, read from its headers (the zip
+/// central directory, or the tar headers while skipping file data) before extracting.
+#[tauri::command]
+async fn estimate_extract(
+    path: String,
+    format: Option<String>,
+    password: Option<String>,
+) -> Result<browse::Estimate, CommandError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let format = format.unwrap_or_else(|| "auto".to_string());
+        let reader = ArchiveReader::open(Path::new(&path), &format)?.with_password(password);
+        let archive_size = std::fs::metadata(&path)?.len();
+        Ok::<_, ExtractError>(browse::estimate(&reader.entries()?, archive_size))
+    })
+    .await?
+    .map_err(CommandError::from)
+}
+
+/// Whether the volume holding `destination` has room for everything in the archive at `path`.
+/// A shortfall comes back as `warning` rather than an error, so the user can still go ahead.
+#[tauri::command]
+async fn check_disk_space(
+    path: String,
+    destination: String,
+    format: Option<String>,
+    password: Option<String>,
+) -> Result<browse::SpaceCheck, CommandError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let format = format.unwrap_or_else(|| "auto".to_string());
+        let reader = ArchiveReader::open(Path::new(&path), &format)?.with_password(password);
+        let archive_size = std::fs::metadata(&path)?.len();
+        let estimate = browse::estimate(&reader.entries()?, archive_size);
+        Ok::<_, CommandError>(browse::check_space(&estimate, Path::new(&destination))?)
+    })
+    .await?
+}
+
+/// The start of a single entry, for previewing text, images and JSON in place. At most
+/// `max_bytes` (and never more than 8 MiB) are returned; `truncated` says whether there is more.
+#[tauri::command]
+async fn preview_entry(
+    path: String,
+    entry: String,
+    format: Option<String>,
+    password: Option<String>,
+    max_bytes: Option<usize>,
+) -> Result<browse::Preview, CommandError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let format = format.unwrap_or_else(|| "auto".to_string());
+        let reader = ArchiveReader::open(Path::new(&path), &format)?.with_password(password);
+        browse::preview(&reader, &entry, max_bytes)
+    })
+    .await?
+    .map_err(CommandError::from)
+}
+
+#[tauri::command]
+fn get_settings(store: tauri::State<'_, settings::Store>) -> settings::Settings {
+    store.get()
+}
+
+#[tauri::command]
+fn set_settings(
+    store: tauri::State<'_, settings::Store>,
+    settings: settings::Settings,
+) -> Result<(), CommandError> {
+    Ok(store.set(settings)?)
+}
+
+/// Finished jobs, newest first.
+#[tauri::command]
+fn get_history(store: tauri::State<'_, history::Store>) -> Vec<history::JobRecord> {
+    store.list()
+}
+
+#[tauri::command]
+fn clear_history(store: tauri::State<'_, history::Store>) -> Result<(), CommandError> {
+    Ok(store.clear()?)
+}
+
+/// Open a file or folder, e.g. the destination once a job is done.
+#[tauri::command]
+fn open_path(path: String) -> Result<(), CommandError> {
+    let path = PathBuf::from(path);
+    if !path.exists() {
+        return Err(
+            CommandError::not_found(format!("{} does not exist", path.display())).with_path(&path),
+        );
+    }
+    Ok(desktop::open(&path)?)
+}
+
+/// Show a file selected in Explorer or Finder (its folder elsewhere).
+#[tauri::command]
+fn reveal_in_file_manager(path: String) -> Result<(), CommandError> {
+    let path = PathBuf::from(path);
+    if !path.exists() {
+        return Err(
+            CommandError::not_found(format!("{} does not exist", path.display())).with_path(&path),
+        );
+    }
+    Ok(desktop::reveal(&path)?)
+}
+
+/// What the app was launched to do from the shell, if anything; `None` after the first call.
+#[tauri::command]
+fn take_launch_request(
+    launch: tauri::State<'_, integration::PendingLaunch>,
+) -> Option<integration::LaunchRequest> {
+    launch.take()
+}
+
+/// Add "open with" associations for archive files and/or the "Extract here" / "Extract to
+/// folder" context-menu entries for the current user.
+#[tauri::command]
+fn register_shell_integration(associations: bool, context_menu: bool) -> Result<(), CommandError> {
+    Ok(integration::register(associations, context_menu)?)
+}
+
+/// Undo [`register_shell_integration`].
+#[tauri::command]
+fn unregister_shell_integration() -> Result<(), CommandError> {
+    Ok(integration::unregister()?)
 }
 
 #[tauri::command]
@@ -171,10 +693,63 @@ fn get_version() -> String {
 
 fn main() {
     tauri::Builder::<tauri::Wry>::new()
+        .manage(jobs::Jobs::default())
+        .manage(thumbnails::Cache::default())
+        .manage(integration::PendingLaunch::new(
+            integration::launch_request(std::env::args().skip(1)),
+        ))
+        .setup(|app| {
+            let path = app
+                .path_resolver()
+                .app_config_dir()
+                .map(|dir| dir.join("settings.json"));
+            app.manage(settings::Store::load(path));
+            let path = app
+                .path_resolver()
+                .app_data_dir()
+                .map(|dir| dir.join("history.json"));
+            app.manage(history::Store::load(path));
+            Ok(())
+        })
+        .on_window_event(|event| {
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event.event() {
+                let window = event.window();
+                let jobs = window.state::<jobs::Jobs>();
+                if jobs.any_running() && window.state::<settings::Store>().get().background_jobs {
+                    // Finish in the background and quit after the last job; see `Jobs::finish`.
+                    api.prevent_close();
+                    jobs.detach();
+                    let _ = window.hide();
+                }
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             detect_format,
             extract_archive,
+            extract_entries,
+            batch_extract,
+            test_archive,
             compress_archive,
+            cancel_job,
+            pause_job,
+            resume_job,
+            check_password_strength,
+            list_archive,
+            preview_entry,
+            estimate_extract,
+            check_disk_space,
+            get_thumbnail,
+            hash_file,
+            hash_archive_entries,
+            get_settings,
+            set_settings,
+            get_history,
+            clear_history,
+            open_path,
+            reveal_in_file_manager,
+            take_launch_request,
+            register_shell_integration,
+            unregister_shell_integration,
             get_version
         ])
         .run(tauri::generate_context!())
@@ -0,0 +1,63 @@
+//! Password checks for creating encrypted archives.
+
+use serde::Serialize;
+
+/// Shorter passwords are refused outright.
+pub const MIN_LENGTH: usize = 8;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Strength {
+    /// 0 (very weak) to 4 (strong).
+    pub score: u8,
+    pub label: &'static str,
+    /// What would make the password stronger.
+    pub hints: Vec<&'static str>,
+}
+
+/// A rough strength estimate from length and character variety.
+pub fn strength(password: &str) -> Strength {
+    let length = password.chars().count();
+    let classes = [
+        password.chars().any(|c| c.is_lowercase()),
+        password.chars().any(|c| c.is_uppercase()),
+        password.chars().any(|c| c.is_ascii_digit()),
+        password.chars().any(|c| !c.is_alphanumeric()),
+    ]
+    .iter()
+    .filter(|&&present| present)
+    .count();
+
+    let mut score: u8 = match length {
+        0..MIN_LENGTH => 0,
+        MIN_LENGTH..=11 => 1,
+        12..=15 => 2,
+        _ => 3,
+    };
+    if length >= MIN_LENGTH && classes >= 3 {
+        score += 1;
+    }
+    let mut hints = Vec::new();
+    if length < 12 {
+        hints.push("Use at least 12 characters");
+    }
+    if classes < 3 {
+        hints.push("Mix upper and lower case, digits and symbols");
+    }
+    let label = ["Very weak", "Weak", "Fair", "Good", "Strong"][score.min(4) as usize];
+    Strength {
+        score: score.min(4),
+        label,
+        hints,
+    }
+}
+
+/// Check a new password and its confirmation before anything is written.
+pub fn validate(password: &str, confirmation: Option<&str>) -> Result<(), String> {
+    if confirmation.is_some_and(|c| c != password) {
+        return Err("Passwords do not match".to_string());
+    }
+    if password.chars().count() < MIN_LENGTH {
+        return Err(format!("Password must be at least {MIN_LENGTH} characters"));
+    }
+    Ok(())
+}
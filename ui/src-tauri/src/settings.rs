@@ -0,0 +1,95 @@
+//! Desktop preferences, kept as `settings.json` in the app config directory.
+
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use zipx_core::containers::{ExtractOptions, OverwritePolicy};
+use zipx_core::pipeline::{CompressOptions, CompressionProfile};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    /// Output folder offered before the user picks one.
+    pub default_destination: Option<PathBuf>,
+    pub overwrite: OverwritePolicy,
+    /// Codec threads when compressing; unset leaves it to the profile.
+    pub threads: Option<u32>,
+    /// Compression preset; an explicit level from the UI still wins.
+    pub profile: Option<CompressionProfile>,
+    pub theme: Theme,
+    /// Keep running jobs going when the window is closed, with a notification as each ends.
+    pub background_jobs: bool,
+}
+
+impl Settings {
+    pub fn apply_extract(&self, options: &mut ExtractOptions) {
+        options.overwrite = self.overwrite;
+    }
+
+    pub fn apply_compress(&self, options: &mut CompressOptions) {
+        options.threads = options.threads.or(self.threads);
+        options.profile = options.profile.or(self.profile);
+    }
+}
+
+pub struct Store {
+    /// `None` when the platform has no config directory; settings then last for the session.
+    path: Option<PathBuf>,
+    current: Mutex<Settings>,
+}
+
+impl Store {
+    /// Read the settings at `path`. A missing or unreadable file gives the defaults, so a bad
+    /// edit never keeps the app from starting.
+    pub fn load(path: Option<PathBuf>) -> Self {
+        let current = path
+            .as_ref()
+            .and_then(|path| match std::fs::read(path) {
+                Ok(bytes) => serde_json::from_slice(&bytes)
+                    .map_err(|e| tracing::warn!("ignoring {}: {e}", path.display()))
+                    .ok(),
+                Err(_) => None,
+            })
+            .unwrap_or_default();
+        Store {
+            path,
+            current: Mutex::new(current),
+        }
+    }
+
+    pub fn get(&self) -> Settings {
+        self.current.lock().map(|s| s.clone()).unwrap_or_default()
+    }
+
+    pub fn set(&self, settings: Settings) -> Result<(), String> {
+        if let Some(path) = &self.path {
+            save_json(path, &settings)?;
+        }
+        if let Ok(mut current) = self.current.lock() {
+            *current = settings;
+        }
+        Ok(())
+    }
+}
+
+/// Write `value` to `path` as JSON, via a rename so a crash mid-save leaves the previous file
+/// intact.
+pub fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    let json = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
+    let staging = path.with_extension("json.tmp");
+    std::fs::write(&staging, json).map_err(|e| e.to_string())?;
+    std::fs::rename(&staging, path).map_err(|e| e.to_string())
+}
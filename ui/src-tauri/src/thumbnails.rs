@@ -0,0 +1,97 @@
+//! Downscaled previews of image entries for the archive browser's grid view.
+
+use std::io::Cursor;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::SystemTime;
+
+use base64::Engine;
+use image::imageops::FilterType;
+use lru::LruCache;
+use serde::Serialize;
+use zipx_core::errors::{ExtractError, Result};
+use zipx_core::reader::ArchiveReader;
+
+use crate::browse::read_capped;
+
+/// Images larger than this are not decoded.
+const MAX_SOURCE_BYTES: usize = 64 * 1024 * 1024;
+const CACHE_ENTRIES: usize = 512;
+pub const DEFAULT_SIZE: u32 = 256;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Thumbnail {
+    pub entry: String,
+    pub width: u32,
+    pub height: u32,
+    pub mime: &'static str,
+    /// Base64 of the encoded thumbnail.
+    pub data: String,
+}
+
+/// Archive path, its modification time (so a changed archive misses), entry and size.
+type Key = (PathBuf, Option<SystemTime>, String, u32);
+
+pub struct Cache(Mutex<LruCache<Key, Thumbnail>>);
+
+impl Default for Cache {
+    fn default() -> Self {
+        Cache(Mutex::new(LruCache::new(
+            NonZeroUsize::new(CACHE_ENTRIES).expect("non-zero cache size"),
+        )))
+    }
+}
+
+impl Cache {
+    /// A thumbnail of `entry` fitting in `size`×`size`, from the cache when possible.
+    pub fn get(
+        &self,
+        reader: &ArchiveReader,
+        archive: &Path,
+        entry: &str,
+        size: u32,
+    ) -> Result<Thumbnail> {
+        let modified = std::fs::metadata(archive)?.modified().ok();
+        let key = (archive.to_path_buf(), modified, entry.to_string(), size);
+        if let Some(hit) = self
+            .0
+            .lock()
+            .ok()
+            .and_then(|mut cache| cache.get(&key).cloned())
+        {
+            return Ok(hit);
+        }
+        let thumbnail = render(reader, entry, size)?;
+        if let Ok(mut cache) = self.0.lock() {
+            cache.put(key, thumbnail.clone());
+        }
+        Ok(thumbnail)
+    }
+}
+
+fn render(reader: &ArchiveReader, entry: &str, size: u32) -> Result<Thumbnail> {
+    let (data, truncated) = read_capped(reader, entry, MAX_SOURCE_BYTES)?;
+    if truncated {
+        return Err(ExtractError::Unsupported(format!(
+            "{entry} is too large to preview"
+        )));
+    }
+    let unreadable = |e: image::ImageError| ExtractError::Unsupported(format!("{entry}: {e}"));
+    let image = image::load_from_memory(&data).map_err(unreadable)?;
+    let small = image.resize(size, size, FilterType::Triangle).to_rgb8();
+    let mut encoded = Vec::new();
+    small
+        .write_to(
+            &mut Cursor::new(&mut encoded),
+            image::ImageOutputFormat::Jpeg(80),
+        )
+        .map_err(unreadable)?;
+    Ok(Thumbnail {
+        entry: entry.to_string(),
+        width: small.width(),
+        height: small.height(),
+        mime: "image/jpeg",
+        data: base64::engine::general_purpose::STANDARD.encode(&encoded),
+    })
+}